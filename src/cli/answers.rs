@@ -0,0 +1,110 @@
+use crate::cli::CliError;
+use crate::parser::{Configuration, Variable};
+use serde::{Deserialize, Serialize};
+use serde_yml::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// Name of the file the answers of a run are recorded to, in the working directory.
+pub const RECORDED_ANSWERS_FILE: &str = ".dynaplate-answers.yaml";
+
+/// Variables that are not recorded, since they describe where the template was applied.
+const UNRECORDED_VARIABLES: [&str; 2] = ["working_dir", "config_dir"];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordedAnswers {
+    pub template: RecordedTemplate,
+    pub answers: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordedTemplate {
+    pub name: String,
+    pub version: String,
+    pub config: String,
+    /// The commit of a template read from a Git repository, used by `update`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+}
+
+/// Parses a `--set name=value` argument.
+pub fn parse_variable_assignment(input: &str) -> Result<(String, String), String> {
+    match input.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("expected NAME=VALUE, got '{}'", input)),
+    }
+}
+
+/// Reads a YAML or JSON file mapping variable names to their values.
+pub fn read_answers_file(path: &Path) -> Result<HashMap<String, Value>, CliError> {
+    let content = fs::read_to_string(path).map_err(|e| CliError::AnswersFileRead {
+        path: path.display().to_string(),
+        source: e,
+    })?;
+
+    serde_yml::from_str(&content).map_err(|e| CliError::AnswersFileParse {
+        path: path.display().to_string(),
+        source: e,
+    })
+}
+
+/// Writes the resolved variables and the template they were used for to the working directory.
+pub fn write_recorded_answers(
+    working_dir: &Path,
+    config: &Configuration,
+    config_location: &str,
+    revision: Option<&str>,
+    variables: &[Variable],
+) -> Result<(), CliError> {
+    let path = working_dir.join(RECORDED_ANSWERS_FILE);
+    let answers = variables
+        .iter()
+        .filter(|variable| !UNRECORDED_VARIABLES.contains(&variable.name.as_str()))
+        .filter_map(|variable| {
+            variable
+                .value
+                .as_ref()
+                .map(|value| serde_yml::to_value(value).map(|value| (variable.name.clone(), value)))
+        })
+        .collect::<Result<BTreeMap<String, Value>, serde_yml::Error>>()
+        .map_err(|e| CliError::AnswersFileParse {
+            path: path.display().to_string(),
+            source: e,
+        })?;
+
+    let recorded = RecordedAnswers {
+        template: RecordedTemplate {
+            name: config.name.clone(),
+            version: config.version.clone(),
+            config: config_location.to_string(),
+            revision: revision.map(String::from),
+        },
+        answers,
+    };
+
+    let content = serde_yml::to_string(&recorded).map_err(|e| CliError::AnswersFileParse {
+        path: path.display().to_string(),
+        source: e,
+    })?;
+
+    fs::write(&path, content).map_err(|e| CliError::AnswersFileWrite {
+        path: path.display().to_string(),
+        source: e,
+    })
+}
+
+/// Reads answers recorded by a previous run.
+pub fn read_recorded_answers(path: &Path) -> Result<RecordedAnswers, CliError> {
+    let content = fs::read_to_string(path).map_err(|e| CliError::AnswersFileRead {
+        path: path.display().to_string(),
+        source: e,
+    })?;
+
+    serde_yml::from_str(&content).map_err(|e| CliError::AnswersFileParse {
+        path: path.display().to_string(),
+        source: e,
+    })
+}
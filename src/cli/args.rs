@@ -1,24 +1,67 @@
-use clap::Parser;
-use std::path::PathBuf;
+use crate::cli::answers::{parse_variable_assignment, RECORDED_ANSWERS_FILE};
+use crate::parser::OnConflict;
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 #[command(name = "Dynaplate")]
 #[command(about = "A tool to automate project templates", version = "1.0")]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+
+    /// `dynaplate <config>` is an alias for `dynaplate run <config>`
+    #[command(flatten)]
+    pub run: RunArgs,
+}
+
+#[derive(clap::Args)]
+pub struct RunArgs {
+    /// Path or URL of the configuration, or `-` to read it from stdin
     #[clap(required = true)]
-    pub config: PathBuf,
+    pub config: Option<PathBuf>,
 
     #[clap(short = 'd', long)]
     pub working_dir: Option<PathBuf>,
 
-    #[clap(short = 't', long, default_value_t, value_enum)]
-    pub config_type: ConfigType,
+    /// Format of the configuration [default: detected from the extension or contents]
+    #[clap(short = 't', long, value_enum)]
+    pub config_type: Option<ConfigType>,
 
     #[clap(short = 'c', long, action = clap::ArgAction::SetTrue)]
     pub clean: Option<bool>,
 
     #[clap(short = 'f', long, action = clap::ArgAction::SetFalse)]
     pub use_filters: Option<bool>,
+
+    /// Abort on the first failing command, unless the command sets its own `on_failure`
+    #[clap(long, action = clap::ArgAction::SetTrue)]
+    pub fail_fast: Option<bool>,
+
+    /// Set a variable without prompting, e.g. `--set project_name=demo` (repeatable)
+    #[clap(short = 's', long = "set", value_name = "NAME=VALUE", value_parser = parse_variable_assignment)]
+    pub set: Vec<(String, String)>,
+
+    /// Read variable values from a YAML or JSON file
+    #[clap(short = 'a', long)]
+    pub answers: Option<PathBuf>,
+
+    /// Never prompt: use defaults and fail when a variable has no value
+    #[clap(long, action = clap::ArgAction::SetTrue)]
+    pub no_input: Option<bool>,
+
+    /// Re-run without prompting, using the answers recorded by a previous run
+    #[clap(long, value_name = "FILE", num_args = 0..=1, default_missing_value = RECORDED_ANSWERS_FILE)]
+    pub replay: Option<PathBuf>,
+
+    /// Print what would be executed and written, without changing anything
+    #[clap(long, action = clap::ArgAction::SetTrue)]
+    pub dry_run: Option<bool>,
+
+    /// What to do with existing files, unless a template file sets its own `on_conflict`
+    #[clap(long, default_value_t, value_enum)]
+    pub on_conflict: OnConflict,
 }
 
 #[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Default)]
@@ -26,4 +69,136 @@ pub enum ConfigType {
     #[default]
     Yaml,
     Json,
+    Toml,
+}
+
+impl ConfigType {
+    /// Determines the format from the extension of `path`, or from its contents when the
+    /// extension is unknown (e.g. when reading from stdin).
+    pub fn detect(path: &Path, contents: &str) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => return ConfigType::Yaml,
+            Some("json") => return ConfigType::Json,
+            Some("toml") => return ConfigType::Toml,
+            _ => {}
+        }
+
+        let first_line = contents
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+            .unwrap_or_default();
+
+        // TOML keys are followed by an equals sign, YAML keys by a colon
+        let is_toml_key = match (first_line.find('='), first_line.find(':')) {
+            (Some(equals), Some(colon)) => equals < colon,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+
+        if first_line.starts_with('{') {
+            ConfigType::Json
+        } else if first_line.starts_with('[') || is_toml_key {
+            ConfigType::Toml
+        } else {
+            ConfigType::Yaml
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Commands {
+    /// Generate a project from a template (the default)
+    Run(RunArgs),
+    /// Check configurations for errors without running anything
+    Validate(ValidateArgs),
+    /// Print the variables, template files and commands of a configuration
+    Inspect(InspectArgs),
+    /// List the templates in the template directory
+    List(ListArgs),
+    /// Create a starter configuration
+    New(NewArgs),
+    /// Re-apply a newer version of the template to a project generated by dynaplate
+    Update(UpdateArgs),
+    /// Print the JSON Schema of configuration files, for editor completion and validation
+    Schema,
+}
+
+#[derive(clap::Args)]
+pub struct ValidateArgs {
+    #[clap(required = true)]
+    pub configs: Vec<PathBuf>,
+
+    /// Format of the configuration [default: detected from the extension or contents]
+    #[clap(short = 't', long, value_enum)]
+    pub config_type: Option<ConfigType>,
+}
+
+#[derive(clap::Args)]
+pub struct InspectArgs {
+    pub config: PathBuf,
+
+    /// Format of the configuration [default: detected from the extension or contents]
+    #[clap(short = 't', long, value_enum)]
+    pub config_type: Option<ConfigType>,
+}
+
+#[derive(clap::Args)]
+pub struct ListArgs {
+    /// Directory to search [default: $DYNAPLATE_TEMPLATE_DIR, or dynaplate/templates in your
+    /// config directory]
+    pub dir: Option<PathBuf>,
+}
+
+#[derive(clap::Args)]
+pub struct NewArgs {
+    /// Path of the configuration to create
+    #[clap(default_value = "template.yaml")]
+    pub path: PathBuf,
+
+    /// Name of the template [default: the name of the file]
+    #[clap(short = 'n', long)]
+    pub name: Option<String>,
+
+    /// Format of the configuration [default: detected from the extension of the path]
+    #[clap(short = 't', long, value_enum)]
+    pub config_type: Option<ConfigType>,
+
+    /// Overwrite the file if it exists
+    #[clap(long, action = clap::ArgAction::SetTrue)]
+    pub force: Option<bool>,
+}
+
+#[derive(clap::Args)]
+pub struct UpdateArgs {
+    /// Configuration of the new template version [default: the recorded configuration]
+    pub config: Option<PathBuf>,
+
+    /// Project directory, containing the answers recorded when it was generated
+    #[clap(short = 'd', long, default_value = ".")]
+    pub working_dir: PathBuf,
+
+    /// Configuration of the template version the project was generated with [default: the
+    /// recorded configuration, at the recorded revision for Git repositories]
+    #[clap(long, value_name = "CONFIG")]
+    pub from: Option<PathBuf>,
+
+    /// Format of the configuration [default: detected from the extension or contents]
+    #[clap(short = 't', long, value_enum)]
+    pub config_type: Option<ConfigType>,
+
+    #[clap(short = 'f', long, action = clap::ArgAction::SetFalse)]
+    pub use_filters: Option<bool>,
+
+    /// Set a variable without prompting, e.g. `--set project_name=demo` (repeatable)
+    #[clap(short = 's', long = "set", value_name = "NAME=VALUE", value_parser = parse_variable_assignment)]
+    pub set: Vec<(String, String)>,
+
+    /// Never prompt for variables the new template version adds
+    #[clap(long, action = clap::ArgAction::SetTrue)]
+    pub no_input: Option<bool>,
+
+    /// Print which files would be updated, without changing anything
+    #[clap(long, action = clap::ArgAction::SetTrue)]
+    pub dry_run: Option<bool>,
 }
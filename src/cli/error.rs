@@ -1,4 +1,26 @@
+use std::io;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
-pub enum CliError {}
+pub enum CliError {
+    #[error("Answers file '{path}' can not be read: {source}")]
+    AnswersFileRead { path: String, source: io::Error },
+
+    #[error("Answers file '{path}' is invalid: {source}")]
+    AnswersFileParse {
+        path: String,
+        source: serde_yml::Error,
+    },
+
+    #[error("Answers file '{path}' can not be written: {source}")]
+    AnswersFileWrite { path: String, source: io::Error },
+
+    #[error("No value for variable(s): {}", .0.join(", "))]
+    MissingVariableValues(Vec<String>),
+
+    #[error("The template version the project was generated with can't be fetched from '{0}', pass its configuration with --from")]
+    PreviousTemplateUnavailable(String),
+
+    #[error("'{0}' already exists, use --force to overwrite it")]
+    ConfigFileExists(String),
+}
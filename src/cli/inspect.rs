@@ -0,0 +1,115 @@
+use crate::parser::traits::condition::ConditionTrait;
+use crate::parser::traits::CommandTrait;
+use crate::parser::{Condition, Configuration};
+
+/// Prints an overview of a configuration: its variables, environment, template files and the
+/// commands with their conditions, in the order they are processed.
+pub fn print_configuration(config: &Configuration) {
+    println!("\x1b[1m{} ({})\x1b[0m", &config.name, &config.version);
+    println!("{}", &config.description);
+
+    println!("\x1b[1mVariables:\x1b[0m");
+    for variable in &config.variables {
+        println!(
+            "  {} ({}): {}",
+            variable.name,
+            variable.var_type.name(),
+            variable.description
+        );
+        if let Some(options) = &variable.options {
+            println!("      options: {}", options.join(", "));
+        }
+        if variable.min.is_some() || variable.max.is_some() {
+            println!("      range:   {}", variable.range_description());
+        }
+        if let Some(pattern) = &variable.pattern {
+            println!("      pattern: {}", pattern);
+        }
+        if variable.min_length.is_some() || variable.max_length.is_some() {
+            println!(
+                "      length:  {}..{}",
+                variable.min_length.unwrap_or(0),
+                variable
+                    .max_length
+                    .map(|max_length| max_length.to_string())
+                    .unwrap_or_default()
+            );
+        }
+        if variable.not_empty {
+            println!("      not empty");
+        }
+        if let Some(default) = &variable.default {
+            println!("      default: {}", default);
+        }
+        if let Some(value) = &variable.value {
+            println!("      value:   {}", value);
+        }
+        if let Some(conditions) = variable.get_conditions() {
+            let conditions: Vec<String> = conditions.iter().map(describe_condition).collect();
+            println!("      when:    {}", conditions.join(" or "));
+        }
+    }
+
+    if !config.environment.is_empty() {
+        println!("\x1b[1mEnvironment:\x1b[0m");
+        for env_var in &config.environment {
+            println!("  {}={}", env_var.name, env_var.value);
+        }
+    }
+
+    print_commands("Pre-commands", &config.pre_commands);
+
+    println!("\x1b[1mTemplate files:\x1b[0m");
+    for file in &config.template_files {
+        println!(
+            "  {} {} -> {}",
+            file.file_type,
+            file.source.display(),
+            file.destination.display()
+        );
+        if !file.render {
+            println!("      not rendered");
+        }
+        if let Some(on_conflict) = file.on_conflict {
+            println!("      on conflict: {}", on_conflict);
+        }
+        if let Some(conditions) = file.get_conditions() {
+            let conditions: Vec<String> = conditions.iter().map(describe_condition).collect();
+            println!("      when: {}", conditions.join(" or "));
+        }
+    }
+
+    print_commands("Dependencies", &config.dependencies);
+    print_commands("Post-commands", &config.post_commands);
+}
+
+fn print_commands<T: CommandTrait + ConditionTrait>(title: &str, commands: &[T]) {
+    println!("\x1b[1m{}:\x1b[0m", title);
+    for command in commands {
+        println!("  {}: {}", command.name(), command.command());
+        if let Some(conditions) = command.get_conditions() {
+            let conditions: Vec<String> = conditions.iter().map(describe_condition).collect();
+            println!("      when: {}", conditions.join(" or "));
+        }
+        if let Some(on_failure) = command.on_failure() {
+            println!("      on failure: {}", on_failure);
+        }
+    }
+}
+
+fn describe_condition(condition: &Condition) -> String {
+    let describe_all = |conditions: &[Condition]| {
+        conditions
+            .iter()
+            .map(describe_condition)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    match condition {
+        Condition::All { all } => format!("all({})", describe_all(all)),
+        Condition::Any { any } => format!("any({})", describe_all(any)),
+        Condition::Not { not } => format!("not({})", describe_condition(not)),
+        Condition::Variable(condition) => condition.describe(),
+    }
+}
@@ -1,7 +1,17 @@
+pub mod answers;
 mod args;
 mod error;
+pub mod inspect;
 pub mod prompt;
+pub mod starter;
 
 pub use args::Args;
+pub use args::Commands;
 pub use args::ConfigType;
+pub use args::InspectArgs;
+pub use args::ListArgs;
+pub use args::NewArgs;
+pub use args::RunArgs;
+pub use args::UpdateArgs;
+pub use args::ValidateArgs;
 pub use error::CliError;
@@ -1,7 +1,8 @@
 use crate::error::AppError;
 use crate::parser::models::VariableType;
-use crate::parser::{Variable, VariableValue};
-use inquire::{Confirm, Select, Text};
+use crate::parser::{ParserError, Variable, VariableValue};
+use inquire::validator::Validation;
+use inquire::{Confirm, CustomType, MultiSelect, Select, Text};
 
 pub fn prompt_for_variable(variable: &Variable) -> Result<Option<VariableValue>, AppError> {
     let prompt_description = match &variable.default {
@@ -14,6 +15,7 @@ pub fn prompt_for_variable(variable: &Variable) -> Result<Option<VariableValue>,
                         "no"
                     }
                 }
+                VariableType::Integer | VariableType::Number => default,
                 _ => &format!("{:?}", default),
             };
             format!("{} (default: {})", variable.description, default_text)
@@ -24,8 +26,10 @@ pub fn prompt_for_variable(variable: &Variable) -> Result<Option<VariableValue>,
     match variable.var_type {
         VariableType::String => {
             let default_value = variable.default.as_deref().unwrap_or("");
+            let rules = variable.clone();
             let answer = Text::new(&prompt_description)
                 .with_default(default_value)
+                .with_validator(move |input: &str| Ok(text_validation(&rules, input)))
                 .prompt()
                 .map_err(AppError::from)?;
 
@@ -52,5 +56,75 @@ pub fn prompt_for_variable(variable: &Variable) -> Result<Option<VariableValue>,
 
             Ok(Some(VariableValue::Select(answer)))
         }
+        VariableType::MultiSelect => {
+            let options = variable.options.clone().unwrap_or_default();
+            let default_selection = variable.default_selection();
+            let default_indices: Vec<usize> = options
+                .iter()
+                .enumerate()
+                .filter(|(_, option)| default_selection.contains(option))
+                .map(|(idx, _)| idx)
+                .collect();
+            let answer = MultiSelect::new(&prompt_description, options)
+                .with_default(&default_indices)
+                .prompt()
+                .map_err(AppError::from)?;
+
+            Ok(Some(VariableValue::MultiSelect(answer)))
+        }
+        VariableType::Integer => {
+            let bounds = variable.clone();
+            let mut prompt = CustomType::<i64>::new(&prompt_description)
+                .with_error_message("Please enter a whole number")
+                .with_validator(move |value: &i64| Ok(range_validation(&bounds, *value as f64)));
+            if let Some(default) = variable
+                .default
+                .as_deref()
+                .and_then(|d| d.trim().parse().ok())
+            {
+                prompt = prompt.with_default(default);
+            }
+            let answer = prompt.prompt().map_err(AppError::from)?;
+
+            Ok(Some(VariableValue::Integer(answer)))
+        }
+        VariableType::Number => {
+            let bounds = variable.clone();
+            let mut prompt = CustomType::<f64>::new(&prompt_description)
+                .with_error_message("Please enter a number")
+                .with_validator(move |value: &f64| Ok(range_validation(&bounds, *value)));
+            if let Some(default) = variable
+                .default
+                .as_deref()
+                .and_then(|d| d.trim().parse().ok())
+            {
+                prompt = prompt.with_default(default);
+            }
+            let answer = prompt.prompt().map_err(AppError::from)?;
+
+            Ok(Some(VariableValue::Number(answer)))
+        }
+    }
+}
+
+/// Rejects text that does not satisfy the validation rules of the variable, so the prompt asks
+/// again.
+fn text_validation(variable: &Variable, input: &str) -> Validation {
+    match variable.check_text(input) {
+        Ok(()) => Validation::Valid,
+        Err(ParserError::VariableValidationFailed { reason, .. }) => {
+            Validation::Invalid(reason.into())
+        }
+        Err(e) => Validation::Invalid(e.to_string().into()),
+    }
+}
+
+/// Rejects numbers outside the range of the variable, so the prompt asks again.
+fn range_validation(variable: &Variable, value: f64) -> Validation {
+    match value.is_finite() && variable.check_range(value).is_ok() {
+        true => Validation::Valid,
+        false => Validation::Invalid(
+            format!("Please enter a number {}", variable.range_description()).into(),
+        ),
     }
 }
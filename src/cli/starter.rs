@@ -0,0 +1,117 @@
+use crate::cli::ConfigType;
+
+const NAME_PLACEHOLDER: &str = "${name}";
+
+const STARTER_YAML: &str = r#"name: ${name}
+description: "Describe what this template sets up"
+version: "1.0.0"
+
+variables:
+  - name: "project_name"
+    description: "Name of the project"
+    type: "string"
+    default: "my-project"
+  - name: "use_git"
+    description: "Initialize a Git repository?"
+    type: "boolean"
+    default: true
+
+template_files:
+  # paths are relative to this file
+  - file_type: "folder"
+    source: "files"
+    destination: "{{working_dir}}"
+
+post_commands:
+  - name: "git_init"
+    command: "git init"
+    conditions:
+      - variable: "use_git"
+        operator: "equals"
+        value: true
+"#;
+
+const STARTER_JSON: &str = r#"{
+  "name": ${name},
+  "description": "Describe what this template sets up",
+  "version": "1.0.0",
+  "variables": [
+    {
+      "name": "project_name",
+      "description": "Name of the project",
+      "type": "string",
+      "default": "my-project"
+    },
+    {
+      "name": "use_git",
+      "description": "Initialize a Git repository?",
+      "type": "boolean",
+      "default": true
+    }
+  ],
+  "template_files": [
+    {
+      "file_type": "folder",
+      "source": "files",
+      "destination": "{{working_dir}}"
+    }
+  ],
+  "post_commands": [
+    {
+      "name": "git_init",
+      "command": "git init",
+      "conditions": [
+        {
+          "variable": "use_git",
+          "operator": "equals",
+          "value": true
+        }
+      ]
+    }
+  ]
+}
+"#;
+
+const STARTER_TOML: &str = r#"name = ${name}
+description = "Describe what this template sets up"
+version = "1.0.0"
+
+[[variables]]
+name = "project_name"
+description = "Name of the project"
+type = "string"
+default = "my-project"
+
+[[variables]]
+name = "use_git"
+description = "Initialize a Git repository?"
+type = "boolean"
+default = true
+
+# paths are relative to this file
+[[template_files]]
+file_type = "folder"
+source = "files"
+destination = "{{working_dir}}"
+
+[[post_commands]]
+name = "git_init"
+command = "git init"
+conditions = [{ variable = "use_git", operator = "equals", value = true }]
+"#;
+
+/// Returns a starter configuration for a template with the given name.
+pub fn starter_config(name: &str, config_type: &ConfigType) -> String {
+    // the name is inserted as a quoted string of the format, so quotes in it are escaped; JSON
+    // strings are valid double-quoted YAML strings
+    let (starter, quoted_name) = match config_type {
+        ConfigType::Yaml => (STARTER_YAML, json_string(name)),
+        ConfigType::Json => (STARTER_JSON, json_string(name)),
+        ConfigType::Toml => (STARTER_TOML, toml::Value::String(name.into()).to_string()),
+    };
+    starter.replace(NAME_PLACEHOLDER, &quoted_name)
+}
+
+fn json_string(value: &str) -> String {
+    serde_json::Value::String(value.into()).to_string()
+}
@@ -23,4 +23,16 @@ pub enum AppError {
 
     #[error("Invalid working directory")]
     InvalidWorkspaceDir,
+
+    #[error("{0} command(s) failed")]
+    CommandsFailed(usize),
+
+    #[error("{0} template file(s) could not be copied")]
+    TemplateFilesFailed(usize),
+
+    #[error("{0} configuration(s) are invalid")]
+    InvalidConfigurations(usize),
+
+    #[error("{0} file(s) have conflicts, resolve the conflict markers")]
+    UpdateConflicts(usize),
 }
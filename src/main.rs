@@ -1,20 +1,51 @@
 use crate::error::AppError::InvalidWorkspaceDir;
 use crate::parser::models::VariableType;
 use crate::parser::VariableValue;
+use crate::utils::UtilsError;
 use crate::{
-    cli::{prompt::prompt_for_variable, Args, ConfigType},
+    cli::{
+        answers::{
+            read_answers_file, read_recorded_answers, write_recorded_answers, RecordedAnswers,
+            RECORDED_ANSWERS_FILE,
+        },
+        inspect::print_configuration,
+        prompt::prompt_for_variable,
+        starter::starter_config,
+        Args, CliError, Commands, ConfigType, InspectArgs, ListArgs, NewArgs, RunArgs, UpdateArgs,
+        ValidateArgs,
+    },
     error::AppError,
-    parser::{models::environment::EnvVarSliceExt, Variable},
+    parser::{
+        composition::{compose, LoadedDocument},
+        models::environment::EnvVarSliceExt,
+        traits::condition::ConditionTrait,
+        Configuration, OnConflict, ParserError, TemplateFile, Variable,
+    },
     utils::{
-        command::execute_commands,
-        file::{copy_template_files, ensure_project_existence},
-        Project,
+        command::{execute_commands, plan_commands},
+        condition::evaluate_conditions,
+        file::{
+            copy_template_files, ensure_project_existence, find_configurations, plan_template_files,
+        },
+        replace_environment_variables,
+        source::{
+            fetch_config, pin_config_location, resolve_relative_location, resolve_relative_sources,
+            template_dir, FetchedConfig,
+        },
+        update::apply_template_update,
+        CommandFailure, Project,
     },
 };
 use clap::Parser;
-use std::fs;
-use std::path::PathBuf;
+use serde_yml::Value;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::{env, fs, io, process};
+
+/// Location that reads the configuration from stdin.
+const STDIN_LOCATION: &str = "-";
 
 mod cli;
 mod error;
@@ -36,20 +67,65 @@ fn main() -> Result<(), AppError> {
 fn run() -> Result<(), AppError> {
     let args = Args::parse();
 
-    let config_string = fs::read_to_string(&args.config).map_err(AppError::ConfigFileRead)?;
+    match &args.command {
+        None => run_template(&args.run),
+        Some(Commands::Run(run_args)) => run_template(run_args),
+        Some(Commands::Validate(validate_args)) => run_validate(validate_args),
+        Some(Commands::Inspect(inspect_args)) => run_inspect(inspect_args),
+        Some(Commands::List(list_args)) => run_list(list_args),
+        Some(Commands::New(new_args)) => run_new(new_args),
+        Some(Commands::Update(update_args)) => run_update(update_args),
+        Some(Commands::Schema) => {
+            println!("{}", parser::json_schema()?);
+            Ok(())
+        }
+    }
+}
 
-    let config = match &args.config_type {
-        ConfigType::Json => parser::from_json(&config_string)?,
-        ConfigType::Yaml => parser::from_yaml(&config_string)?,
-    };
+fn run_template(args: &RunArgs) -> Result<(), AppError> {
+    // unwrap allowed, since the config is required by clap
+    let config_location = args.config.clone().unwrap();
+    let (config, fetched_config) = load_config(&config_location, args.config_type)?;
 
     println!("\x1b[1m{} ({})\x1b[0m", &config.name, &config.version);
     println!("\x1b[1m{}\x1b[0m", &config.description);
 
-    let envs = &config.environment.to_env_map();
+    let recorded = match &args.replay {
+        Some(path) => {
+            let recorded = read_recorded_answers(path)?;
+            if recorded.template.name != config.name {
+                eprintln!(
+                    "Warning: replaying answers recorded for template '{}'",
+                    recorded.template.name
+                );
+            }
+            Some(recorded)
+        }
+        None => None,
+    };
+
+    // when replaying, the project lives where its answers were recorded
+    let working_dir = args.working_dir.clone().or_else(|| {
+        args.replay.as_ref().map(|path| match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("./"),
+        })
+    });
 
-    let default_variables = add_default_variables(args.working_dir)?;
-    let variables = gather_variables(&config.variables, default_variables)?;
+    let default_variables = add_default_variables(working_dir, &fetched_config.base)?;
+    let answers = collect_answers(
+        &config.variables,
+        &default_variables,
+        args.replay.as_deref().zip(recorded.as_ref()),
+        args.answers.as_deref(),
+        &args.set,
+    )?;
+    let variables = gather_variables(
+        &config.variables,
+        default_variables,
+        &answers,
+        args.no_input.unwrap() || recorded.is_some(),
+    )?;
 
     // unwrap allowed, since variable working_dir is added in code
     let working_dir_var = variables
@@ -66,59 +142,536 @@ fn run() -> Result<(), AppError> {
                 Ok(path) => Ok(path),
                 Err(_) => Err(InvalidWorkspaceDir),
             },
-            VariableValue::Boolean(_) | VariableValue::Select(_) => Err(InvalidWorkspaceDir),
+            VariableValue::Boolean(_)
+            | VariableValue::Select(_)
+            | VariableValue::MultiSelect(_)
+            | VariableValue::Integer(_)
+            | VariableValue::Number(_) => Err(InvalidWorkspaceDir),
         },
     }?;
 
+    let envs = replace_environment_variables(&config.environment.to_env_map(), &variables)?;
+
     let project = Project {
         working_dir,
-        envs: envs.to_owned(),
+        envs,
         variables: variables.clone(),
         clean: args.clean.unwrap(),
+        fail_fast: args.fail_fast.unwrap(),
+        on_conflict: args.on_conflict,
+        no_input: args.no_input.unwrap(),
+    };
+
+    if args.dry_run.unwrap() {
+        return print_plan(&config, &project, &args.use_filters.unwrap());
+    }
+
+    let mut failures = Vec::new();
+    let result = generate_project(&config, &project, &args.use_filters.unwrap(), &mut failures);
+
+    if !failures.is_empty() {
+        print_failure_summary(&failures);
+    }
+    let failed_files = result?;
+
+    write_recorded_answers(
+        &project.working_dir,
+        &config,
+        &recorded_config_location(&config_location),
+        fetched_config.revision.as_deref(),
+        &project.variables,
+    )?;
+
+    match (failures.is_empty(), failed_files) {
+        (true, 0) => Ok(()),
+        (true, failed_files) => Err(AppError::TemplateFilesFailed(failed_files)),
+        (false, _) => Err(AppError::CommandsFailed(failures.len())),
+    }
+}
+
+/// Loads every configuration and reports whether it is valid. All configurations are checked, even
+/// when one of them is invalid.
+fn run_validate(args: &ValidateArgs) -> Result<(), AppError> {
+    let mut invalid = 0;
+    for location in &args.configs {
+        match load_config(location, args.config_type) {
+            Ok((config, _)) => println!(
+                "{}: '{}' ({}) is valid",
+                location.display(),
+                config.name,
+                config.version
+            ),
+            Err(e) => {
+                eprintln!("{}: {}", location.display(), e);
+                invalid += 1;
+            }
+        }
+    }
+
+    match invalid {
+        0 => Ok(()),
+        invalid => Err(AppError::InvalidConfigurations(invalid)),
+    }
+}
+
+fn run_inspect(args: &InspectArgs) -> Result<(), AppError> {
+    let (config, _) = load_config(&args.config, args.config_type)?;
+    print_configuration(&config);
+    Ok(())
+}
+
+/// Lists the configurations found in the template directory, with their name and description.
+fn run_list(args: &ListArgs) -> Result<(), AppError> {
+    let dir = args.dir.clone().unwrap_or_else(template_dir);
+    println!("\x1b[1mTemplates in {}:\x1b[0m", dir.display());
+
+    for path in find_configurations(&dir) {
+        let relative_path = path.strip_prefix(&dir).unwrap_or(&path);
+        match load_config(&path, None) {
+            Ok((config, _)) => println!(
+                "  {} ({}) - {}\n      {}",
+                config.name,
+                config.version,
+                config.description,
+                relative_path.display()
+            ),
+            Err(_) => eprintln!("  {} (invalid)", relative_path.display()),
+        }
+    }
+    Ok(())
+}
+
+/// Writes a starter configuration, with a `files` folder for its template files next to it.
+fn run_new(args: &NewArgs) -> Result<(), AppError> {
+    if args.path.exists() && !args.force.unwrap() {
+        return Err(CliError::ConfigFileExists(args.path.display().to_string()).into());
+    }
+
+    let name = match &args.name {
+        Some(name) => name.clone(),
+        None => args
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or("template".to_string()),
+    };
+
+    let files_dir = match args.path.parent() {
+        Some(parent) => parent.join("files"),
+        None => PathBuf::from("files"),
+    };
+    fs::create_dir_all(&files_dir).map_err(UtilsError::from)?;
+    let readme = files_dir.join("README.md");
+    if !readme.exists() {
+        fs::write(&readme, "# {{project_name}}\n").map_err(UtilsError::from)?;
+    }
+    let config_type = args
+        .config_type
+        .unwrap_or_else(|| ConfigType::detect(&args.path, ""));
+    fs::write(&args.path, starter_config(&name, &config_type)).map_err(UtilsError::from)?;
+
+    println!("Created {} for template '{}'", args.path.display(), name);
+    println!("Template files go in {}", files_dir.display());
+    Ok(())
+}
+
+/// Renders the template version a project was generated with and the new version, and applies the
+/// differences between them to the project. Commands are not executed.
+fn run_update(args: &UpdateArgs) -> Result<(), AppError> {
+    let answers_path = args.working_dir.join(RECORDED_ANSWERS_FILE);
+    let recorded = read_recorded_answers(&answers_path)?;
+    let unavailable = || CliError::PreviousTemplateUnavailable(recorded.template.config.clone());
+
+    let old_location = match (&args.from, &recorded.template.revision) {
+        (Some(from), _) => from.clone(),
+        (None, Some(revision)) => pin_config_location(&recorded.template.config, revision)
+            .map(PathBuf::from)
+            .ok_or_else(unavailable)?,
+        (None, None) => return Err(unavailable().into()),
+    };
+    let new_location = args
+        .config
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(&recorded.template.config));
+
+    let (old_config, old_fetched) = load_config(&old_location, args.config_type)?;
+    let (new_config, new_fetched) = load_config(&new_location, args.config_type)?;
+
+    println!(
+        "\x1b[1mUpdating {} from {} to {}\x1b[0m",
+        &new_config.name, &recorded.template.version, &new_config.version
+    );
+
+    let render_dir = env::temp_dir().join(format!("dynaplate-update-{}", process::id()));
+    let old_dir = render_dir.join("old");
+    let new_dir = render_dir.join("new");
+    let recorded_answers = Some((answers_path.as_path(), &recorded));
+
+    // the old version is rendered with the recorded answers only, so it matches the project
+    let default_variables = add_default_variables(Some(old_dir.clone()), &old_fetched.base)?;
+    let answers = collect_answers(
+        &old_config.variables,
+        &default_variables,
+        recorded_answers,
+        None,
+        &[],
+    )?;
+    let old_variables = gather_variables(&old_config.variables, default_variables, &answers, true)?;
+
+    let default_variables = add_default_variables(Some(new_dir.clone()), &new_fetched.base)?;
+    let answers = collect_answers(
+        &new_config.variables,
+        &default_variables,
+        recorded_answers,
+        None,
+        &args.set,
+    )?;
+    let new_variables = gather_variables(
+        &new_config.variables,
+        default_variables,
+        &answers,
+        args.no_input.unwrap(),
+    )?;
+
+    if render_dir.exists() {
+        fs::remove_dir_all(&render_dir).map_err(UtilsError::from)?;
+    }
+    let use_filters = args.use_filters.unwrap();
+    let result = render_template(&old_config, &old_variables, &old_dir, &use_filters)
+        .and_then(|_| render_template(&new_config, &new_variables, &new_dir, &use_filters))
+        .and_then(|_| {
+            println!("\x1b[1mChanges:\x1b[0m");
+            Ok(apply_template_update(
+                &old_dir,
+                &new_dir,
+                &args.working_dir,
+                args.dry_run.unwrap(),
+            )?)
+        });
+    let _ = fs::remove_dir_all(&render_dir);
+    let conflicts = result?;
+
+    if args.dry_run.unwrap() {
+        return Ok(());
+    }
+
+    write_recorded_answers(
+        &args.working_dir,
+        &new_config,
+        &recorded_config_location(&new_location),
+        new_fetched.revision.as_deref(),
+        &new_variables,
+    )?;
+
+    match conflicts.is_empty() {
+        true => Ok(()),
+        false => {
+            eprintln!("\x1b[1mConflicts:\x1b[0m");
+            for conflict in &conflicts {
+                eprintln!("  - {}", conflict.display());
+            }
+            Err(AppError::UpdateConflicts(conflicts.len()))
+        }
+    }
+}
+
+/// Renders the template files below `{{working_dir}}` into `output_dir`. Files written elsewhere
+/// are not part of the project, so they are left out of updates.
+fn render_template(
+    config: &Configuration,
+    variables: &[Variable],
+    output_dir: &Path,
+    use_filters: &bool,
+) -> Result<(), AppError> {
+    let files: Vec<TemplateFile> = config
+        .template_files
+        .iter()
+        .filter(|file| file.destination.starts_with("{{working_dir}}"))
+        .cloned()
+        .collect();
+
+    ensure_project_existence(&output_dir.to_path_buf())?;
+    match copy_template_files(
+        &files,
+        variables,
+        use_filters,
+        OnConflict::Overwrite,
+        &false,
+    )? {
+        0 => Ok(()),
+        failed_files => Err(AppError::TemplateFilesFailed(failed_files)),
+    }
+}
+
+/// Fetches and parses a configuration, composed with the configurations it extends and includes.
+fn load_config(
+    location: &Path,
+    config_type: Option<ConfigType>,
+) -> Result<(Configuration, FetchedConfig), AppError> {
+    let (root, fetched) = load_document(location, config_type)?;
+
+    let config = compose(root, &mut |reference: &str, base: &str| {
+        let location = resolve_relative_location(reference, base);
+        match load_document(Path::new(&location), None) {
+            Ok((document, _)) => Ok(document),
+            Err(AppError::Parse(e)) => Err(ParserError::InvalidInclude {
+                location,
+                source: Box::new(e),
+            }
+            .into()),
+            Err(e) => Err(e),
+        }
+    })?;
+
+    Ok((config, fetched))
+}
+
+/// Fetches and parses a single configuration document, resolving its relative template sources
+/// against its location. The document is read from stdin when the location is `-`.
+fn load_document(
+    location: &Path,
+    config_type: Option<ConfigType>,
+) -> Result<(LoadedDocument, FetchedConfig), AppError> {
+    let (config_string, fetched) = match location == Path::new(STDIN_LOCATION) {
+        true => {
+            let mut config_string = String::new();
+            io::stdin()
+                .read_to_string(&mut config_string)
+                .map_err(AppError::ConfigFileRead)?;
+            let fetched = FetchedConfig {
+                path: location.to_path_buf(),
+                base: env::current_dir()
+                    .map_err(AppError::ConfigFileRead)?
+                    .display()
+                    .to_string(),
+                revision: None,
+            };
+            (config_string, fetched)
+        }
+        false => {
+            let fetched = fetch_config(location)?;
+            let config_string =
+                fs::read_to_string(&fetched.path).map_err(AppError::ConfigFileRead)?;
+            (config_string, fetched)
+        }
+    };
+
+    let config_type =
+        config_type.unwrap_or_else(|| ConfigType::detect(&fetched.path, &config_string));
+    let mut document = match config_type {
+        ConfigType::Json => parser::from_json(&config_string)?,
+        ConfigType::Yaml => parser::from_yaml(&config_string)?,
+        ConfigType::Toml => parser::from_toml(&config_string)?,
+    };
+    resolve_relative_sources(&mut document.template_files, &fetched.base);
+
+    let loaded = LoadedDocument {
+        location: fs::canonicalize(&fetched.path)
+            .unwrap_or(fetched.path.clone())
+            .display()
+            .to_string(),
+        base: fetched.base.clone(),
+        document,
     };
+    Ok((loaded, fetched))
+}
 
-    execute_commands(&config.pre_commands, &project);
+/// Local configurations are recorded by their absolute path; remote locations can't be
+/// canonicalized and are recorded as given.
+fn recorded_config_location(location: &Path) -> String {
+    fs::canonicalize(location)
+        .unwrap_or(location.to_path_buf())
+        .display()
+        .to_string()
+}
+
+/// Runs the commands and copies the template files; returns the number of template files that
+/// could not be copied.
+fn generate_project(
+    config: &Configuration,
+    project: &Project,
+    use_filters: &bool,
+    failures: &mut Vec<CommandFailure>,
+) -> Result<usize, AppError> {
+    execute_commands(&config.pre_commands, project, failures)?;
     ensure_project_existence(&project.working_dir)?;
-    copy_template_files(&config.template_files, &variables, &args.use_filters.unwrap())?;
-    execute_commands(&config.dependencies, &project);
-    execute_commands(&config.post_commands, &project);
+    let failed_files = copy_template_files(
+        &config.template_files,
+        &project.variables,
+        use_filters,
+        project.on_conflict,
+        &project.no_input,
+    )?;
+    execute_commands(&config.dependencies, project, failures)?;
+    execute_commands(&config.post_commands, project, failures)?;
+    Ok(failed_files)
+}
+
+fn print_plan(
+    config: &Configuration,
+    project: &Project,
+    use_filters: &bool,
+) -> Result<(), AppError> {
+    println!("\x1b[1mDry run: nothing will be executed or written\x1b[0m");
+    println!("\x1b[1mPre-commands:\x1b[0m");
+    plan_commands(&config.pre_commands, project);
+    println!("\x1b[1mTemplate files:\x1b[0m");
+    plan_template_files(
+        &config.template_files,
+        &project.variables,
+        use_filters,
+        project.on_conflict,
+        &project.no_input,
+    )?;
+    println!("\x1b[1mDependencies:\x1b[0m");
+    plan_commands(&config.dependencies, project);
+    println!("\x1b[1mPost-commands:\x1b[0m");
+    plan_commands(&config.post_commands, project);
     Ok(())
 }
 
-fn add_default_variables(working_dir: Option<PathBuf>) -> Result<Vec<Variable>, AppError> {
+fn print_failure_summary(failures: &[CommandFailure]) {
+    eprintln!("\x1b[1mFailed commands:\x1b[0m");
+    for failure in failures {
+        eprintln!("  - {}: {}", failure.name, failure.reason);
+    }
+}
+
+fn add_default_variables(
+    working_dir: Option<PathBuf>,
+    config_dir: &str,
+) -> Result<Vec<Variable>, AppError> {
     let working_dir_var = Variable {
         name: "working_dir".to_string(),
         description: "The working directory in which commands will run by default".to_string(),
         default: Some("./".to_string()),
         var_type: VariableType::String,
         options: None,
+        min: None,
+        max: None,
+        pattern: None,
+        min_length: None,
+        max_length: None,
+        not_empty: false,
+        error_message: None,
         value: working_dir.map(|value| VariableValue::String(value.display().to_string())),
+        conditions: None,
+    };
+
+    let config_dir_var = Variable {
+        name: "config_dir".to_string(),
+        description: "The directory containing the configuration file".to_string(),
+        default: None,
+        var_type: VariableType::String,
+        options: None,
+        min: None,
+        max: None,
+        pattern: None,
+        min_length: None,
+        max_length: None,
+        not_empty: false,
+        error_message: None,
+        value: Some(VariableValue::String(config_dir.to_string())),
+        conditions: None,
     };
 
-    Ok(vec![working_dir_var])
+    Ok(vec![working_dir_var, config_dir_var])
 }
 
+/// Collects the values given through `--replay`, `--answers` and `--set` (in increasing order of
+/// precedence), checked against the type of the variable they belong to.
+fn collect_answers(
+    variables: &[Variable],
+    default_variables: &[Variable],
+    recorded: Option<(&Path, &RecordedAnswers)>,
+    answers_file: Option<&Path>,
+    set: &[(String, String)],
+) -> Result<HashMap<String, VariableValue>, AppError> {
+    let find_variable = |name: &str| {
+        default_variables
+            .iter()
+            .chain(variables.iter())
+            .find(|variable| variable.name == name)
+    };
+
+    let mut answers = HashMap::new();
+    let mut add_answers = |file: &Path, file_answers: Vec<(String, Value)>| {
+        for (name, answer) in file_answers {
+            match find_variable(&name) {
+                Some(variable) => {
+                    answers.insert(name, variable.parse_answer(&answer)?);
+                }
+                None => eprintln!(
+                    "Warning: ignoring answer for unknown variable '{}' in '{}'",
+                    name,
+                    file.display()
+                ),
+            }
+        }
+        Ok::<(), AppError>(())
+    };
+
+    if let Some((path, recorded)) = recorded {
+        add_answers(path, recorded.answers.clone().into_iter().collect())?;
+    }
+
+    if let Some(path) = answers_file {
+        add_answers(path, read_answers_file(path)?.into_iter().collect())?;
+    }
+
+    for (name, raw) in set {
+        let variable =
+            find_variable(name).ok_or_else(|| ParserError::VariableDoesNotExist(name.clone()))?;
+        answers.insert(name.clone(), variable.parse_value(raw)?);
+    }
+
+    Ok(answers)
+}
+
+/// Determines the value of every variable in order, so the conditions of a variable can be
+/// evaluated against the variables before it. A variable whose conditions do not hold is not
+/// prompted for and gets its default, or stays unset.
 fn gather_variables(
     variables: &[Variable],
     default_variables: Vec<Variable>,
+    answers: &HashMap<String, VariableValue>,
+    no_input: bool,
 ) -> Result<Vec<Variable>, AppError> {
-    default_variables
-        .iter()
-        .chain(variables.iter())
-        .map(|variable| {
-            let user_input = match &variable.value {
-                Some(value) => Some(value.clone()),
-                None => prompt_for_variable(variable)?,
-            };
+    let mut missing = Vec::new();
+    let mut gathered: Vec<Variable> = Vec::with_capacity(default_variables.len() + variables.len());
 
-            Ok(Variable {
-                name: variable.name.clone(),
-                description: variable.description.to_string(),
-                default: variable.default.clone(),
-                var_type: variable.var_type.clone(),
-                options: variable.options.clone(),
-                value: user_input,
-            })
-        })
-        .collect()
+    for variable in default_variables.iter().chain(variables.iter()) {
+        let applicable = match variable.get_conditions() {
+            Some(conditions) => evaluate_conditions(Some(conditions), &gathered)?.is_none(),
+            None => true,
+        };
+
+        let user_input = match answers.get(&variable.name).or(variable.value.as_ref()) {
+            Some(value) => Some(value.clone()),
+            None if !applicable => variable
+                .default
+                .as_deref()
+                .map(|default| variable.parse_value(default))
+                .transpose()?,
+            None if no_input => match (&variable.default, &variable.var_type) {
+                (Some(default), _) => Some(variable.parse_value(default)?),
+                (None, VariableType::MultiSelect) => Some(VariableValue::MultiSelect(vec![])),
+                (None, _) => {
+                    missing.push(variable.name.clone());
+                    None
+                }
+            },
+            None => prompt_for_variable(variable)?,
+        };
+
+        gathered.push(Variable {
+            value: user_input,
+            ..variable.clone()
+        });
+    }
+
+    match missing.is_empty() {
+        true => Ok(gathered),
+        false => Err(CliError::MissingVariableValues(missing).into()),
+    }
 }
@@ -0,0 +1,240 @@
+use crate::parser::error::ValidationIssue;
+use crate::parser::validation::{validate_configuration, validate_document};
+use crate::parser::{Configuration, ConfigurationDocument, ParserError};
+
+/// A configuration document together with where it was loaded from.
+pub struct LoadedDocument {
+    /// Identifies the document, used to detect cycles.
+    pub location: String,
+    /// Location that references in the document are resolved against.
+    pub base: String,
+    pub document: ConfigurationDocument,
+}
+
+/// Resolves the `extends` and `include` references of `root` and validates the result. `load`
+/// loads the document a reference points to, given the base of the referencing document.
+///
+/// A document is merged on top of the configuration it extends, after its includes: the
+/// extended configuration comes first, then each include in order, then the document itself.
+pub fn compose<E, F>(root: LoadedDocument, load: &mut F) -> Result<Configuration, E>
+where
+    E: From<ParserError>,
+    F: FnMut(&str, &str) -> Result<LoadedDocument, E>,
+{
+    let mut issues = Vec::new();
+    let configuration = resolve(root, load, &mut Vec::new(), &mut issues)?.into_configuration()?;
+    validate_configuration(&configuration, issues)?;
+    Ok(configuration)
+}
+
+fn resolve<E, F>(
+    loaded: LoadedDocument,
+    load: &mut F,
+    chain: &mut Vec<String>,
+    issues: &mut Vec<ValidationIssue>,
+) -> Result<ConfigurationDocument, E>
+where
+    E: From<ParserError>,
+    F: FnMut(&str, &str) -> Result<LoadedDocument, E>,
+{
+    if chain.contains(&loaded.location) {
+        chain.push(loaded.location);
+        return Err(ParserError::CompositionCycle(chain.join(" -> ")).into());
+    }
+
+    // checked before merging, as duplicates within a document would replace each other
+    let location = (!chain.is_empty()).then_some(loaded.location.as_str());
+    validate_document(&loaded.document, location, issues);
+    chain.push(loaded.location);
+
+    let mut document = loaded.document;
+    let mut composed = match document.extends.take() {
+        Some(reference) => resolve(load(&reference, &loaded.base)?, load, chain, issues)?,
+        None => ConfigurationDocument::default(),
+    };
+
+    for reference in std::mem::take(&mut document.include) {
+        let included = resolve(load(&reference, &loaded.base)?, load, chain, issues)?;
+        composed.merge(included);
+    }
+    composed.merge(document);
+
+    chain.pop();
+    Ok(composed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = r#"
+name: base
+description: base template
+version: 1.0.0
+variables:
+  - { name: a, description: base a, type: string }
+  - { name: b, description: base b, type: string }
+pre_commands:
+  - { name: build, command: make }
+  - { name: test, command: make test }
+template_files:
+  - { name: readme, source: base.md, destination: README.md, file_type: file }
+  - { source: base, destination: "{{working_dir}}", file_type: folder }
+"#;
+
+    fn loaded(location: &str, contents: &str) -> LoadedDocument {
+        LoadedDocument {
+            location: location.to_string(),
+            base: String::new(),
+            document: serde_yml::from_str(contents).unwrap(),
+        }
+    }
+
+    /// Composes the `root` document, loading references from `documents` by their location.
+    fn compose_documents(
+        root: &str,
+        documents: &[(&str, &str)],
+    ) -> Result<Configuration, ParserError> {
+        compose(loaded("root", root), &mut |reference: &str, _: &str| {
+            let (location, contents) = documents
+                .iter()
+                .find(|(location, _)| *location == reference)
+                .unwrap_or_else(|| panic!("unknown document {}", reference));
+            Ok(loaded(location, contents))
+        })
+    }
+
+    fn names<'a, T>(items: &'a [T], name: impl Fn(&'a T) -> &'a str) -> Vec<&'a str> {
+        items.iter().map(name).collect()
+    }
+
+    #[test]
+    fn replaces_items_in_place_and_appends_new_items() {
+        let root = r#"
+extends: base
+name: app
+variables:
+  - { name: c, description: root c, type: string }
+  - { name: a, description: root a, type: string }
+pre_commands:
+  - { name: build, command: cargo build }
+"#;
+        let configuration = compose_documents(root, &[("base", BASE)]).unwrap();
+
+        assert_eq!(configuration.name, "app");
+        assert_eq!(configuration.version, "1.0.0");
+        assert_eq!(
+            names(&configuration.variables, |v| &v.name),
+            ["a", "b", "c"]
+        );
+        assert_eq!(configuration.variables[0].description, "root a");
+        assert_eq!(
+            names(&configuration.pre_commands, |c| &c.command),
+            ["cargo build", "make test"]
+        );
+    }
+
+    #[test]
+    fn merges_the_extended_configuration_then_includes_then_the_document() {
+        let one = r#"
+pre_commands:
+  - { name: build, command: one }
+  - { name: lint, command: one }
+  - { name: format, command: one }
+"#;
+        let two = r#"
+pre_commands:
+  - { name: lint, command: two }
+  - { name: format, command: two }
+"#;
+        let root = r#"
+extends: base
+include: [one, two]
+pre_commands:
+  - { name: format, command: root }
+"#;
+        let configuration =
+            compose_documents(root, &[("base", BASE), ("one", one), ("two", two)]).unwrap();
+
+        assert_eq!(
+            names(&configuration.pre_commands, |c| &c.name),
+            ["build", "test", "lint", "format"]
+        );
+        assert_eq!(
+            names(&configuration.pre_commands, |c| &c.command),
+            ["one", "make test", "two", "root"]
+        );
+    }
+
+    #[test]
+    fn items_of_the_same_document_do_not_replace_each_other() {
+        let root = r#"
+extends: base
+pre_commands:
+  - { name: setup, command: first }
+  - { name: setup, command: second }
+template_files:
+  - { source: root, destination: "{{working_dir}}", file_type: folder }
+  - { name: readme, source: root.md, destination: README.md, file_type: file }
+  - { source: root, destination: "{{working_dir}}", file_type: folder }
+"#;
+        let configuration = compose_documents(root, &[("base", BASE)]).unwrap();
+
+        assert_eq!(
+            names(&configuration.pre_commands, |c| &c.command),
+            ["make", "make test", "first", "second"]
+        );
+        // unnamed template files are always appended
+        let sources: Vec<_> = configuration
+            .template_files
+            .iter()
+            .map(|file| file.source.display().to_string())
+            .collect();
+        assert_eq!(sources, ["root.md", "base", "root", "root"]);
+    }
+
+    #[test]
+    fn reports_duplicate_variables_of_each_document_with_other_problems() {
+        let fragment = r#"
+variables:
+  - { name: d, description: first, type: string }
+  - { name: d, description: second, type: string }
+"#;
+        let root = r#"
+extends: base
+include: [fragment]
+variables:
+  - { name: a, description: first, type: string }
+  - { name: a, description: second, type: string }
+pre_commands:
+  - { name: empty, command: "" }
+"#;
+        let error = compose_documents(root, &[("base", BASE), ("fragment", fragment)]);
+
+        let Err(ParserError::InvalidConfiguration(issues)) = error else {
+            panic!("expected an invalid configuration, got {:?}", error);
+        };
+        let paths: Vec<_> = issues.iter().map(|issue| issue.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "variables[1].name",
+                "fragment: variables[1].name",
+                "pre_commands[2].command"
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let fragment = "extends: root";
+        let root = "include: [fragment]";
+        let error = compose_documents(root, &[("fragment", fragment), ("root", root)]);
+
+        assert!(
+            matches!(&error, Err(ParserError::CompositionCycle(chain)) if chain == "root -> fragment -> root"),
+            "{:?}",
+            error
+        );
+    }
+}
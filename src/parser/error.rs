@@ -1,3 +1,4 @@
+use std::fmt;
 use thiserror::Error;
 
 pub type ParseResult<T> = Result<T, ParserError>;
@@ -10,15 +11,87 @@ pub enum ParserError {
     #[error("JSON parsing error: {0}")]
     JsonParseError(#[from] serde_json::Error),
 
+    #[error("TOML parsing error: {0}")]
+    TomlParseError(#[from] toml::de::Error),
+
     #[error("Variable '{0}' does not exist")]
     VariableDoesNotExist(String),
 
     #[error("Variable '{name}' has incorrect value: {val}")]
     VariableHasIncorrectValue { name: String, val: String },
 
+    #[error("Variable '{name}' has value {val}, expected a value {range}")]
+    VariableOutOfRange {
+        name: String,
+        val: String,
+        range: String,
+    },
+
+    #[error("Variable '{name}' has invalid value '{val}': {reason}")]
+    VariableValidationFailed {
+        name: String,
+        val: String,
+        reason: String,
+    },
+
+    #[error("Invalid validation rule: {0}")]
+    InvalidValidationRule(String),
+
+    #[error("Invalid range: {0}")]
+    InvalidRange(String),
+
     #[error("Specified default value is invalid: {0}")]
     InvalidDefaultValue(String),
 
+    #[error(
+        "Condition of variable '{variable}' refers to '{name}', which is not declared before it"
+    )]
+    ConditionOnLaterVariable { variable: String, name: String },
+
+    #[error("Invalid condition: {0}")]
+    InvalidCondition(String),
+
     #[error("Command '{name}' is empty")]
     CommandIsEmpty { name: String },
+
+    #[error("Variable '{0}' is defined more than once")]
+    DuplicateVariable(String),
+
+    #[error("Invalid placeholder '{{{{{0}}}}}', expected '{{{{name}}}}' or '{{{{name|join:separator}}}}'")]
+    InvalidPlaceholder(String),
+
+    #[error("Configuration has no '{0}', set it or inherit it with 'extends'")]
+    MissingConfigurationField(String),
+
+    #[error("Configuration '{location}' is invalid: {source}")]
+    InvalidInclude {
+        location: String,
+        source: Box<ParserError>,
+    },
+
+    #[error("Configuration includes itself: {0}")]
+    CompositionCycle(String),
+
+    #[error("Configuration has {} problem(s):{}", .0.len(), .0.iter().map(|issue| format!("\n  - {}", issue)).collect::<String>())]
+    InvalidConfiguration(Vec<ValidationIssue>),
+}
+
+/// A problem found while validating the configuration, with the path of the offending item, e.g.
+/// `dependencies[3].conditions[0].variable`.
+#[derive(Debug)]
+pub struct ValidationIssue {
+    pub path: String,
+    pub error: ParserError,
+}
+
+impl ValidationIssue {
+    pub fn new(path: String, error: ParserError) -> Self {
+        Self { path, error }
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.error)
+    }
 }
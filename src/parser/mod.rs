@@ -1,120 +1,46 @@
+pub mod composition;
 pub(crate) mod error;
 pub mod models;
+pub mod placeholder;
 pub mod traits;
+pub mod validation;
 
 pub use models::Command;
+pub use models::CommandOutput;
 pub use models::Condition;
 pub use models::ConditionOperator;
 pub use models::Configuration;
+pub use models::ConfigurationDocument;
 pub use models::Dependency;
 pub use models::EnvVar;
+pub use models::OnConflict;
+pub use models::OnFailure;
 pub use models::TemplateFile;
 pub use models::TemplateFileType;
 pub use models::Variable;
+pub use models::VariableCondition;
 pub use models::VariableValue;
 
 pub use error::ParserError;
 
 use crate::parser::error::ParseResult;
-use crate::parser::models::VariableType;
 
-pub fn from_yaml(input: &str) -> ParseResult<Configuration> {
-    let configuration = serde_yml::from_str(input).map_err(ParserError::YamlParseError)?;
+// documents are validated once `extends` and `include` are resolved, see `composition::compose`
 
-    match validate_configuration(&configuration) {
-        Ok(_) => Ok(configuration),
-        Err(e) => Err(e),
-    }
+pub fn from_yaml(input: &str) -> ParseResult<ConfigurationDocument> {
+    serde_yml::from_str(input).map_err(ParserError::YamlParseError)
 }
 
-pub fn from_json(input: &str) -> ParseResult<Configuration> {
-    let configuration = serde_json::from_str(input).map_err(ParserError::JsonParseError)?;
-
-    match validate_configuration(&configuration) {
-        Ok(_) => Ok(configuration),
-        Err(e) => Err(e),
-    }
+pub fn from_json(input: &str) -> ParseResult<ConfigurationDocument> {
+    serde_json::from_str(input).map_err(ParserError::JsonParseError)
 }
 
-fn validate_configuration(configuration: &Configuration) -> ParseResult<()> {
-    for variable in &configuration.variables {
-        if let Some(default_value) = &variable.default {
-            match variable.var_type {
-                VariableType::String => {
-                    // No validation required for String type.
-                }
-                VariableType::Boolean => {
-                    if default_value != "true" && default_value != "false" {
-                        return Err(ParserError::InvalidDefaultValue(format!(
-                            "Invalid default value '{}' for Boolean variable '{}'. Expected 'true' or 'false'.",
-                            default_value, variable.name
-                        )));
-                    }
-                }
-                VariableType::Select => {
-                    if let Some(options) = &variable.options {
-                        if !options.contains(default_value) {
-                            return Err(ParserError::InvalidDefaultValue(format!(
-                                "Invalid default value '{}' for Select variable '{}'. Expected one of: {:?}.",
-                                default_value, variable.name, options
-                            )));
-                        }
-
-                        // Check if given value is valid option
-                        if let Some(value) = &variable.value {
-                            match value {
-                                VariableValue::String(str) => {
-                                    if !options.contains(str) {
-                                        return Err(ParserError::VariableHasIncorrectValue {
-                                            name: variable.name.clone(),
-                                            val: str.to_string(),
-                                        });
-                                    }
-                                }
-                                VariableValue::Boolean(_) => {}
-                                VariableValue::Select(_) => {}
-                            }
-                        }
-                    } else {
-                        return Err(ParserError::InvalidDefaultValue(format!(
-                            "Select variable '{}' has no options defined.",
-                            variable.name
-                        )));
-                    }
-                }
-            }
-        }
-    }
-
-    if let Some(command) = configuration
-        .pre_commands
-        .iter()
-        .find(|s| s.command.is_empty())
-    {
-        return Err(ParserError::CommandIsEmpty {
-            name: command.name.clone(),
-        });
-    }
-
-    if let Some(command) = configuration
-        .post_commands
-        .iter()
-        .find(|s| s.command.is_empty())
-    {
-        return Err(ParserError::CommandIsEmpty {
-            name: command.name.clone(),
-        });
-    }
-
-    if let Some(command) = configuration
-        .dependencies
-        .iter()
-        .find(|s| s.command.is_empty())
-    {
-        return Err(ParserError::CommandIsEmpty {
-            name: command.name.clone(),
-        });
-    }
+pub fn from_toml(input: &str) -> ParseResult<ConfigurationDocument> {
+    toml::from_str(input).map_err(ParserError::TomlParseError)
+}
 
-    Ok(())
+/// JSON Schema of configuration files, generated from the models.
+pub fn json_schema() -> ParseResult<String> {
+    let schema = schemars::schema_for!(ConfigurationDocument);
+    serde_json::to_string_pretty(&schema).map_err(ParserError::JsonParseError)
 }
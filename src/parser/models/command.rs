@@ -1,14 +1,23 @@
 use crate::parser::traits::condition::ConditionTrait;
-use crate::parser::traits::CommandTrait;
-use crate::parser::Condition;
+use crate::parser::traits::{CommandTrait, MergeTrait};
+use crate::parser::{CommandOutput, Condition, OnFailure};
+use schemars::JsonSchema;
 use serde::Deserialize;
 use std::fmt;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct Command {
     pub name: String,
+    /// Shell command, may contain `{{variable}}` placeholders.
     pub command: String,
+    /// The command only runs when any of the conditions holds.
     pub conditions: Option<Vec<Condition>>,
+    pub on_failure: Option<OnFailure>,
+    #[serde(default)]
+    pub output: CommandOutput,
+    /// Connect the terminal to the command's stdin, for interactive commands.
+    #[serde(default)]
+    pub stdin: bool,
 }
 
 impl fmt::Display for Command {
@@ -29,6 +38,18 @@ impl CommandTrait for Command {
     fn name(&self) -> &str {
         &self.name
     }
+
+    fn on_failure(&self) -> Option<OnFailure> {
+        self.on_failure
+    }
+
+    fn output(&self) -> CommandOutput {
+        self.output
+    }
+
+    fn stdin(&self) -> bool {
+        self.stdin
+    }
 }
 
 impl ConditionTrait for Command {
@@ -36,3 +57,9 @@ impl ConditionTrait for Command {
         self.conditions.as_deref()
     }
 }
+
+impl MergeTrait for Command {
+    fn merge_key(&self) -> Option<&str> {
+        Some(&self.name)
+    }
+}
@@ -0,0 +1,29 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::fmt;
+
+/// How the output of a command is shown while it runs.
+#[derive(Debug, Deserialize, JsonSchema, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandOutput {
+    /// Hand the terminal to the command, as if it was run directly.
+    Inherit,
+    /// Print output live, each line prefixed with the command name.
+    #[default]
+    Stream,
+    /// Collect output and print it once the command has finished.
+    Capture,
+    /// Hide output; stderr is still reported when the command fails.
+    Silent,
+}
+
+impl fmt::Display for CommandOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandOutput::Inherit => write!(f, "inherit"),
+            CommandOutput::Stream => write!(f, "stream"),
+            CommandOutput::Capture => write!(f, "capture"),
+            CommandOutput::Silent => write!(f, "silent"),
+        }
+    }
+}
@@ -1,54 +1,163 @@
 use crate::parser::{ConditionOperator, VariableValue};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
 use serde::de::Error;
 use serde::{Deserialize, Deserializer};
 use serde_yml::Value;
+use std::cmp::Ordering;
 use std::fmt;
 
-#[derive(Debug, Deserialize)]
-pub struct Condition {
+/// A condition is either a single variable check or a group of nested conditions.
+#[derive(Debug, JsonSchema, Clone)]
+#[serde(untagged)]
+pub enum Condition {
+    All { all: Vec<Condition> },
+    Any { any: Vec<Condition> },
+    Not { not: Box<Condition> },
+    Variable(VariableCondition),
+}
+
+impl<'de> Deserialize<'de> for Condition {
+    /// Picks the variant from the keys instead of trying each one, so the error of the intended
+    /// variant, such as an unknown operator, reaches the user.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut value = Value::deserialize(deserializer)?;
+        let group = value.as_mapping_mut().and_then(|mapping| {
+            ["all", "any", "not"]
+                .into_iter()
+                .find_map(|key| mapping.remove(key).map(|conditions| (key, conditions)))
+        });
+
+        let condition = match group {
+            Some(("all", all)) => serde_yml::from_value(all).map(|all| Condition::All { all }),
+            Some(("any", any)) => serde_yml::from_value(any).map(|any| Condition::Any { any }),
+            Some((_, not)) => serde_yml::from_value(not).map(|not| Condition::Not { not }),
+            None => serde_yml::from_value(value).map(Condition::Variable),
+        };
+        condition.map_err(Error::custom)
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Clone)]
+pub struct VariableCondition {
     pub variable: String,
     pub operator: ConditionOperator,
-    #[serde(deserialize_with = "custom_deserialize_condition_value")]
-    pub value: VariableValue,
+    /// Not used by `is_set` and `is_empty`.
+    #[serde(default, deserialize_with = "custom_deserialize_condition_value")]
+    #[schemars(schema_with = "condition_value_schema")]
+    pub value: Option<VariableValue>,
 }
 
 pub fn custom_deserialize_condition_value<'de, D>(
     deserializer: D,
-) -> Result<VariableValue, D::Error>
+) -> Result<Option<VariableValue>, D::Error>
 where
     D: Deserializer<'de>,
 {
     let value: Value = Value::deserialize(deserializer)?;
 
     match value {
-        Value::String(s) => Ok(VariableValue::String(s)),
-        Value::Bool(b) => Ok(VariableValue::Boolean(b)),
+        Value::Null => Ok(None),
+        Value::String(s) => Ok(Some(VariableValue::String(s))),
+        // numbers are compared by their text, or numerically by `greater_than`/`less_than`
+        Value::Number(n) => Ok(Some(VariableValue::String(n.to_string()))),
+        Value::Bool(b) => Ok(Some(VariableValue::Boolean(b))),
         Value::Sequence(arr) => {
             let strings: Result<Vec<String>, D::Error> = arr
                 .into_iter()
-                .map(|v| {
-                    v.as_str()
-                        .map(String::from)
-                        .ok_or_else(|| Error::custom("Expected a string in array"))
+                .map(|v| match v {
+                    Value::String(s) => Ok(s),
+                    Value::Number(n) => Ok(n.to_string()),
+                    _ => Err(Error::custom("Expected a string or number in array")),
                 })
                 .collect();
 
-            // TODO: add support for multiselect
-            strings?
-                .first()
-                .map(|s| VariableValue::String(s.clone()))
-                .ok_or_else(|| Error::custom("Expected at least one string in the array"))
+            Ok(Some(VariableValue::MultiSelect(strings?)))
+        }
+        _ => Err(Error::custom(
+            "Expected a string, number, boolean, or array",
+        )),
+    }
+}
+
+fn condition_value_schema(_: &mut SchemaGenerator) -> Schema {
+    serde_json::from_value(serde_json::json!({
+        "anyOf": [
+            { "type": "string" },
+            { "type": "number" },
+            { "type": "boolean" },
+            { "type": "array", "items": { "type": ["string", "number"] } },
+        ]
+    }))
+    .expect("condition value schema is valid")
+}
+
+/// A value that `greater_than` and `less_than` can order: a number, or else a semantic version
+/// (an optional leading `v` is ignored).
+#[derive(Debug, PartialEq)]
+pub enum Comparable {
+    Number(f64),
+    Version(semver::Version),
+}
+
+impl Comparable {
+    pub fn parse(text: &str) -> Option<Comparable> {
+        let text = text.trim();
+        if let Ok(number) = text.parse::<f64>() {
+            return Some(Comparable::Number(number));
+        }
+        semver::Version::parse(text.strip_prefix('v').unwrap_or(text))
+            .ok()
+            .map(Comparable::Version)
+    }
+}
+
+impl PartialOrd for Comparable {
+    /// Numbers and versions are not comparable with each other.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Comparable::Number(a), Comparable::Number(b)) => a.partial_cmp(b),
+            (Comparable::Version(a), Comparable::Version(b)) => a.partial_cmp(b),
+            _ => None,
+        }
+    }
+}
+
+impl VariableCondition {
+    /// Short form such as `'version' GREATER_THAN 1.2.0`, used in reports.
+    pub fn describe(&self) -> String {
+        match &self.value {
+            Some(value) => format!("'{}' {} {}", self.variable, self.operator, value),
+            None => format!("'{}' {}", self.variable, self.operator),
         }
-        _ => Err(Error::custom("Expected a string, boolean, or array")),
     }
 }
 
 impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::All { all } => write!(f, "Condition:\n  All: {:?}", all),
+            Condition::Any { any } => write!(f, "Condition:\n  Any: {:?}", any),
+            Condition::Not { not } => write!(f, "Condition:\n  Not: {:?}", not),
+            Condition::Variable(condition) => write!(f, "{}", condition),
+        }
+    }
+}
+
+impl fmt::Display for VariableCondition {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "Condition:\n  Variable: {}\n  Operator: {:?}\n  Value: {}",
-            self.variable, self.operator, self.value
-        )
+            "Condition:\n  Variable: {}\n  Operator: {:?}",
+            self.variable, self.operator
+        )?;
+        match &self.value {
+            Some(value) => write!(f, "\n  Value: {}", value),
+            None => Ok(()),
+        }
     }
 }
@@ -1,11 +1,36 @@
+use schemars::JsonSchema;
 use serde::Deserialize;
 use std::fmt;
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "lowercase")]
+#[derive(Debug, Deserialize, JsonSchema, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
 pub enum ConditionOperator {
     Equals,
+    #[serde(alias = "notequals")]
     NotEquals,
+    /// A multiselect contains the option (or all options of a list); a string contains the text.
+    Contains,
+    /// The value is one of the options in a list.
+    In,
+    NotIn,
+    /// The value matches a regular expression.
+    Matches,
+    StartsWith,
+    EndsWith,
+    /// Compares numbers, or semantic versions such as `1.10.0`.
+    GreaterThan,
+    LessThan,
+    /// The variable has a non-empty value; takes no `value`.
+    IsSet,
+    /// The variable has no value, an empty text or an empty selection; takes no `value`.
+    IsEmpty,
+}
+
+impl ConditionOperator {
+    /// Whether the operator compares against the `value` of the condition.
+    pub fn takes_value(&self) -> bool {
+        !matches!(self, ConditionOperator::IsSet | ConditionOperator::IsEmpty)
+    }
 }
 
 impl fmt::Display for ConditionOperator {
@@ -13,6 +38,16 @@ impl fmt::Display for ConditionOperator {
         match self {
             ConditionOperator::Equals => write!(f, "EQUALS"),
             ConditionOperator::NotEquals => write!(f, "NOT_EQUALS"),
+            ConditionOperator::Contains => write!(f, "CONTAINS"),
+            ConditionOperator::In => write!(f, "IN"),
+            ConditionOperator::NotIn => write!(f, "NOT_IN"),
+            ConditionOperator::Matches => write!(f, "MATCHES"),
+            ConditionOperator::StartsWith => write!(f, "STARTS_WITH"),
+            ConditionOperator::EndsWith => write!(f, "ENDS_WITH"),
+            ConditionOperator::GreaterThan => write!(f, "GREATER_THAN"),
+            ConditionOperator::LessThan => write!(f, "LESS_THAN"),
+            ConditionOperator::IsSet => write!(f, "IS_SET"),
+            ConditionOperator::IsEmpty => write!(f, "IS_EMPTY"),
         }
     }
 }
@@ -0,0 +1,85 @@
+use crate::parser::traits::MergeTrait;
+use crate::parser::{
+    Command, Configuration, Dependency, EnvVar, ParserError, TemplateFile, Variable,
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// A configuration file as written, before `extends` and `include` are resolved. Every field is
+/// optional, so a fragment only has to contain the lists it contributes.
+#[derive(Debug, Deserialize, JsonSchema, Default)]
+#[schemars(title = "Dynaplate configuration")]
+pub struct ConfigurationDocument {
+    /// Configuration this one is based on.
+    pub extends: Option<String>,
+    /// Fragments merged into this configuration, in order.
+    #[serde(default)]
+    pub include: Vec<String>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub version: Option<String>,
+    #[serde(default)]
+    pub pre_commands: Vec<Command>,
+    #[serde(default)]
+    pub environment: Vec<EnvVar>,
+    #[serde(default)]
+    pub dependencies: Vec<Dependency>,
+    #[serde(default)]
+    pub variables: Vec<Variable>,
+    #[serde(default)]
+    pub template_files: Vec<TemplateFile>,
+    #[serde(default)]
+    pub post_commands: Vec<Command>,
+}
+
+impl ConfigurationDocument {
+    /// Merges `other` on top of this document: its name, description and version replace the
+    /// current ones, and list items replace items with the same name or are appended.
+    pub fn merge(&mut self, other: ConfigurationDocument) {
+        self.name = other.name.or(self.name.take());
+        self.description = other.description.or(self.description.take());
+        self.version = other.version.or(self.version.take());
+        merge_list(&mut self.pre_commands, other.pre_commands);
+        merge_list(&mut self.environment, other.environment);
+        merge_list(&mut self.dependencies, other.dependencies);
+        merge_list(&mut self.variables, other.variables);
+        merge_list(&mut self.template_files, other.template_files);
+        merge_list(&mut self.post_commands, other.post_commands);
+    }
+
+    /// Turns a fully composed document into a configuration, which needs a name, description and
+    /// version.
+    pub fn into_configuration(self) -> Result<Configuration, ParserError> {
+        let missing = |field: &str| ParserError::MissingConfigurationField(field.to_string());
+
+        Ok(Configuration {
+            name: self.name.ok_or_else(|| missing("name"))?,
+            description: self.description.ok_or_else(|| missing("description"))?,
+            version: self.version.ok_or_else(|| missing("version"))?,
+            pre_commands: self.pre_commands,
+            environment: self.environment,
+            dependencies: self.dependencies,
+            variables: self.variables,
+            template_files: self.template_files,
+            post_commands: self.post_commands,
+        })
+    }
+}
+
+/// An item with the same key as an item of the base list replaces it in place, keeping the order
+/// of the base list; other items are appended. Items are only matched against the base list, so
+/// items of the same document never replace each other.
+fn merge_list<T: MergeTrait>(base: &mut Vec<T>, items: Vec<T>) {
+    let base_len = base.len();
+    for item in items {
+        let existing = item.merge_key().and_then(|key| {
+            base[..base_len]
+                .iter()
+                .position(|b| b.merge_key() == Some(key))
+        });
+        match existing {
+            Some(idx) => base[idx] = item,
+            None => base.push(item),
+        }
+    }
+}
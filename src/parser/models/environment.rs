@@ -1,10 +1,13 @@
+use crate::parser::traits::MergeTrait;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::{env, fmt};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct EnvVar {
     pub name: String,
+    /// May contain `{{variable}}` placeholders.
     pub value: String,
 }
 
@@ -14,6 +17,12 @@ impl fmt::Display for EnvVar {
     }
 }
 
+impl MergeTrait for EnvVar {
+    fn merge_key(&self) -> Option<&str> {
+        Some(&self.name)
+    }
+}
+
 pub trait EnvVarSliceExt {
     fn to_env_map(&self) -> HashMap<String, String>;
 }
@@ -1,9 +1,13 @@
 pub mod command;
+pub mod command_output;
 pub mod condition;
 pub mod condition_operator;
 pub mod configuration;
+pub mod configuration_document;
 pub mod dependency;
 pub mod environment;
+pub mod on_conflict;
+pub mod on_failure;
 pub mod template_file;
 pub mod template_file_type;
 pub mod variable;
@@ -11,11 +15,15 @@ mod variable_type;
 pub mod variable_value;
 
 pub use command::Command;
-pub use condition::Condition;
+pub use command_output::CommandOutput;
+pub use condition::{Condition, VariableCondition};
 pub use condition_operator::ConditionOperator;
 pub use configuration::Configuration;
+pub use configuration_document::ConfigurationDocument;
 pub use dependency::Dependency;
 pub use environment::EnvVar;
+pub use on_conflict::OnConflict;
+pub use on_failure::OnFailure;
 pub use template_file::TemplateFile;
 pub use template_file_type::TemplateFileType;
 pub use variable::Variable;
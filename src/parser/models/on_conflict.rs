@@ -0,0 +1,42 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::fmt;
+
+/// What to do when a template file would be written over an existing file with different contents.
+#[derive(Debug, Deserialize, JsonSchema, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OnConflict {
+    /// Replace the existing file.
+    #[default]
+    Overwrite,
+    /// Keep the existing file.
+    Skip,
+    /// Show a diff and ask whether to replace the existing file.
+    Prompt,
+    /// Stop processing and exit with an error.
+    Error,
+    /// Move the existing file to `<name>.bak` before writing.
+    Backup,
+}
+
+impl OnConflict {
+    /// The policy to apply when nobody can be asked (`--no-input`): `prompt` fails like `error`.
+    pub fn without_input(self) -> OnConflict {
+        match self {
+            OnConflict::Prompt => OnConflict::Error,
+            on_conflict => on_conflict,
+        }
+    }
+}
+
+impl fmt::Display for OnConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OnConflict::Overwrite => write!(f, "overwrite"),
+            OnConflict::Skip => write!(f, "skip"),
+            OnConflict::Prompt => write!(f, "prompt"),
+            OnConflict::Error => write!(f, "error"),
+            OnConflict::Backup => write!(f, "backup"),
+        }
+    }
+}
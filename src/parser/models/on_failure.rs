@@ -0,0 +1,25 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::fmt;
+
+/// What to do when a command exits unsuccessfully.
+#[derive(Debug, Deserialize, JsonSchema, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OnFailure {
+    /// Stop processing and exit with an error.
+    Abort,
+    /// Keep going, but exit with an error once all commands have run.
+    Continue,
+    /// Keep going and only print a warning.
+    Warn,
+}
+
+impl fmt::Display for OnFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OnFailure::Abort => write!(f, "abort"),
+            OnFailure::Continue => write!(f, "continue"),
+            OnFailure::Warn => write!(f, "warn"),
+        }
+    }
+}
@@ -1,13 +1,54 @@
-use crate::parser::TemplateFileType;
+use crate::parser::traits::condition::ConditionTrait;
+use crate::parser::traits::MergeTrait;
+use crate::parser::{Condition, OnConflict, TemplateFileType};
+use schemars::JsonSchema;
 use serde::Deserialize;
 use std::fmt;
 use std::path::PathBuf;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema, Clone)]
 pub struct TemplateFile {
+    /// Identifies the file when configurations are composed with `extends` and `include`.
+    pub name: Option<String>,
+    /// Local path (relative to the configuration), `git+<url>#<ref>:<subdirectory>` or URL.
     pub source: PathBuf,
     pub destination: PathBuf,
     pub file_type: TemplateFileType,
+    /// Replace `{{variable}}` placeholders in the contents of text files.
+    #[serde(default = "default_render")]
+    pub render: bool,
+    /// Expected SHA-256 checksum of a source downloaded over HTTP(S).
+    pub sha256: Option<String>,
+    /// What to do with existing files, overrides `--on-conflict`.
+    pub on_conflict: Option<OnConflict>,
+    /// The file is only copied when any of the conditions holds.
+    pub conditions: Option<Vec<Condition>>,
+}
+
+fn default_render() -> bool {
+    true
+}
+
+impl TemplateFile {
+    /// Name of the template file for messages, its destination if it has no name.
+    pub fn label(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => self.destination.display().to_string(),
+        }
+    }
+}
+
+impl ConditionTrait for TemplateFile {
+    fn get_conditions(&self) -> Option<&[Condition]> {
+        self.conditions.as_deref()
+    }
+}
+
+impl MergeTrait for TemplateFile {
+    fn merge_key(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
 }
 
 impl fmt::Display for TemplateFile {
@@ -1,7 +1,8 @@
+use schemars::JsonSchema;
 use serde::Deserialize;
 use std::fmt;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum TemplateFileType {
     Folder,
@@ -1,17 +1,252 @@
+use crate::parser::error::ParseResult;
 use crate::parser::models::variable_type::VariableType;
-use crate::parser::VariableValue;
-use serde::Deserialize;
+use crate::parser::traits::condition::ConditionTrait;
+use crate::parser::traits::MergeTrait;
+use crate::parser::{Condition, ParserError, VariableValue};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+use serde_yml::Value;
 use std::fmt;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Clone)]
 pub struct Variable {
     pub name: String,
+    /// Shown when prompting for the variable.
     pub description: String,
+    /// Value suggested when prompting, and used with `--no-input`; a list for multiselect.
+    #[serde(default, deserialize_with = "custom_deserialize_default")]
+    #[schemars(with = "Option<VariableValue>")]
     pub default: Option<String>,
     #[serde(rename = "type")]
     pub var_type: VariableType,
+    /// Options of a select or multiselect variable.
     pub options: Option<Vec<String>>,
+    /// Smallest allowed value of an integer or number variable.
+    pub min: Option<f64>,
+    /// Largest allowed value of an integer or number variable.
+    pub max: Option<f64>,
+    /// Regular expression a string value has to match, e.g. `^[a-z][a-z0-9_-]*$`.
+    pub pattern: Option<String>,
+    /// Minimum number of characters of a string value.
+    pub min_length: Option<usize>,
+    /// Maximum number of characters of a string value.
+    pub max_length: Option<usize>,
+    /// Reject empty (or only whitespace) string values.
+    #[serde(default)]
+    pub not_empty: bool,
+    /// Shown instead of the generated message when a string value is rejected.
+    pub error_message: Option<String>,
+    /// Fixed value; the variable is not prompted for.
     pub value: Option<VariableValue>,
+    /// The variable is only prompted for when any of the conditions holds, which may only refer
+    /// to variables declared before it. Otherwise it gets its default, or stays unset.
+    pub conditions: Option<Vec<Condition>>,
+}
+
+impl Variable {
+    /// Returns the default selection of a multi-select variable, which is stored comma separated.
+    pub fn default_selection(&self) -> Vec<String> {
+        self.default
+            .as_deref()
+            .map(|default| {
+                default
+                    .split(',')
+                    .map(|option| option.trim().to_string())
+                    .filter(|option| !option.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Converts a textual value (e.g. from `--set` or a default) to a value of this variable's
+    /// type. Multi-select values are comma separated.
+    pub fn parse_value(&self, raw: &str) -> ParseResult<VariableValue> {
+        match self.var_type {
+            VariableType::String => {
+                self.check_text(raw)?;
+                Ok(VariableValue::String(raw.to_string()))
+            }
+            VariableType::Boolean => match raw.trim() {
+                "true" => Ok(VariableValue::Boolean(true)),
+                "false" => Ok(VariableValue::Boolean(false)),
+                _ => Err(self.incorrect_value(raw)),
+            },
+            VariableType::Select => {
+                self.check_option(raw)?;
+                Ok(VariableValue::Select(raw.to_string()))
+            }
+            VariableType::MultiSelect => {
+                let selection: Vec<String> = raw
+                    .split(',')
+                    .map(|option| option.trim().to_string())
+                    .filter(|option| !option.is_empty())
+                    .collect();
+                self.parse_selection(selection)
+            }
+            VariableType::Integer => {
+                let value: i64 = raw.trim().parse().map_err(|_| self.incorrect_value(raw))?;
+                self.check_range(value as f64)?;
+                Ok(VariableValue::Integer(value))
+            }
+            VariableType::Number => {
+                let value: f64 = raw
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|value: &f64| value.is_finite())
+                    .ok_or_else(|| self.incorrect_value(raw))?;
+                self.check_range(value)?;
+                Ok(VariableValue::Number(value))
+            }
+        }
+    }
+
+    /// Checks a string value against `not_empty`, `min_length`, `max_length` and `pattern`.
+    pub fn check_text(&self, value: &str) -> ParseResult<()> {
+        match self.text_violation(value) {
+            Some(reason) => Err(ParserError::VariableValidationFailed {
+                name: self.name.clone(),
+                val: value.to_string(),
+                reason: self.error_message.clone().unwrap_or(reason),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn text_violation(&self, value: &str) -> Option<String> {
+        let length = value.chars().count();
+        if self.not_empty && value.trim().is_empty() {
+            return Some("it must not be empty".to_string());
+        }
+        if let Some(min_length) = self.min_length.filter(|min_length| length < *min_length) {
+            return Some(format!(
+                "it must be at least {} characters long",
+                min_length
+            ));
+        }
+        if let Some(max_length) = self.max_length.filter(|max_length| length > *max_length) {
+            return Some(format!("it must be at most {} characters long", max_length));
+        }
+        // an invalid pattern is reported when the configuration is validated
+        let pattern = self.pattern.as_deref()?;
+        match Regex::new(pattern) {
+            Ok(regex) if !regex.is_match(value) => {
+                Some(format!("it must match the pattern '{}'", pattern))
+            }
+            _ => None,
+        }
+    }
+
+    /// Checks that a number lies within `min` and `max` (both inclusive).
+    pub fn check_range(&self, value: f64) -> ParseResult<()> {
+        let below_min = self.min.is_some_and(|min| value < min);
+        let above_max = self.max.is_some_and(|max| value > max);
+        match below_min || above_max {
+            true => Err(ParserError::VariableOutOfRange {
+                name: self.name.clone(),
+                val: value.to_string(),
+                range: self.range_description(),
+            }),
+            false => Ok(()),
+        }
+    }
+
+    /// Describes the allowed range, e.g. `between 1 and 65535` or `at least 0`.
+    pub fn range_description(&self) -> String {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) => format!("between {} and {}", min, max),
+            (Some(min), None) => format!("at least {}", min),
+            (None, Some(max)) => format!("at most {}", max),
+            (None, None) => "any number".to_string(),
+        }
+    }
+
+    /// Converts a value read from an answers file to a value of this variable's type.
+    pub fn parse_answer(&self, answer: &Value) -> ParseResult<VariableValue> {
+        match answer {
+            Value::String(s) => self.parse_value(s),
+            Value::Bool(b) => self.parse_value(&b.to_string()),
+            Value::Number(n) => self.parse_value(&n.to_string()),
+            Value::Sequence(arr) if self.var_type == VariableType::MultiSelect => {
+                let selection = arr
+                    .iter()
+                    .map(|v| {
+                        v.as_str()
+                            .map(String::from)
+                            .ok_or_else(|| self.incorrect_value(&format!("{:?}", v)))
+                    })
+                    .collect::<ParseResult<Vec<String>>>()?;
+                self.parse_selection(selection)
+            }
+            _ => Err(self.incorrect_value(&format!("{:?}", answer))),
+        }
+    }
+
+    pub fn parse_selection(&self, selection: Vec<String>) -> ParseResult<VariableValue> {
+        for option in &selection {
+            self.check_option(option)?;
+        }
+        Ok(VariableValue::MultiSelect(selection))
+    }
+
+    fn check_option(&self, option: &str) -> ParseResult<()> {
+        match &self.options {
+            Some(options) if options.iter().any(|o| o == option) => Ok(()),
+            _ => Err(self.incorrect_value(option)),
+        }
+    }
+
+    fn incorrect_value(&self, val: &str) -> ParserError {
+        ParserError::VariableHasIncorrectValue {
+            name: self.name.clone(),
+            val: val.to_string(),
+        }
+    }
+}
+
+/// Accepts scalar defaults as well as lists (for multi-select variables), which are joined with
+/// commas.
+pub fn custom_deserialize_default<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: Value = Value::deserialize(deserializer)?;
+
+    match value {
+        Value::Null => Ok(None),
+        Value::String(s) => Ok(Some(s)),
+        Value::Bool(b) => Ok(Some(b.to_string())),
+        Value::Number(n) => Ok(Some(n.to_string())),
+        Value::Sequence(arr) => {
+            let strings: Result<Vec<String>, D::Error> = arr
+                .into_iter()
+                .map(|v| {
+                    v.as_str()
+                        .map(String::from)
+                        .ok_or_else(|| Error::custom("Expected a string in array"))
+                })
+                .collect();
+
+            Ok(Some(strings?.join(",")))
+        }
+        _ => Err(Error::custom(
+            "Expected a string, boolean, number, or array",
+        )),
+    }
+}
+
+impl ConditionTrait for Variable {
+    fn get_conditions(&self) -> Option<&[Condition]> {
+        self.conditions.as_deref()
+    }
+}
+
+impl MergeTrait for Variable {
+    fn merge_key(&self) -> Option<&str> {
+        Some(&self.name)
+    }
 }
 
 impl fmt::Display for Variable {
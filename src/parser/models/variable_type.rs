@@ -1,21 +1,39 @@
+use schemars::JsonSchema;
 use serde::Deserialize;
 use std::fmt::{Display, Formatter};
 
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Deserialize, JsonSchema, Clone, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum VariableType {
     String,
     Boolean,
     Select,
+    MultiSelect,
+    /// A whole number, optionally limited by `min` and `max`.
+    Integer,
+    /// A (decimal) number, optionally limited by `min` and `max`.
+    Number,
 }
 
-impl Display for VariableType {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let variant_name = match self {
+impl VariableType {
+    pub fn name(&self) -> &'static str {
+        match self {
             VariableType::String => "String",
             VariableType::Boolean => "Boolean",
             VariableType::Select => "Select",
-        };
-        write!(f, "Variable type: {}", variant_name)
+            VariableType::MultiSelect => "MultiSelect",
+            VariableType::Integer => "Integer",
+            VariableType::Number => "Number",
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, VariableType::Integer | VariableType::Number)
+    }
+}
+
+impl Display for VariableType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Variable type: {}", self.name())
     }
 }
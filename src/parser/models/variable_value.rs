@@ -1,12 +1,29 @@
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Serialize, PartialEq, Clone)]
 #[serde(untagged)]
 pub enum VariableValue {
     String(String),
     Boolean(bool),
     Select(String),
+    MultiSelect(Vec<String>),
+    Integer(i64),
+    Number(f64),
+}
+
+impl VariableValue {
+    /// The numeric value of an integer or number; text is parsed, so numeric conditions can also
+    /// be used with string variables.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            VariableValue::Integer(i) => Some(*i as f64),
+            VariableValue::Number(n) => Some(*n),
+            VariableValue::String(s) | VariableValue::Select(s) => s.trim().parse().ok(),
+            VariableValue::Boolean(_) | VariableValue::MultiSelect(_) => None,
+        }
+    }
 }
 
 impl fmt::Display for VariableValue {
@@ -20,6 +37,9 @@ impl fmt::Display for VariableValue {
                 // o.iter().fold(String::new(), |acc, x| acc + x + ", ")
                 o
             ),
+            VariableValue::MultiSelect(o) => write!(f, "MULTISELECT ({})", o.join(", ")),
+            VariableValue::Integer(i) => write!(f, "INTEGER({})", i),
+            VariableValue::Number(n) => write!(f, "NUMBER({})", n),
         }
     }
 }
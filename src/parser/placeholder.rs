@@ -0,0 +1,54 @@
+use std::ops::Range;
+
+/// A `{{...}}` placeholder found in a command, path or file.
+pub struct Placeholder<'a> {
+    /// Byte range of the placeholder in the input, including the braces.
+    pub range: Range<usize>,
+    /// Everything between the braces.
+    pub expression: &'a str,
+}
+
+impl<'a> Placeholder<'a> {
+    /// Splits the expression (e.g. `features|join:, `) into the variable name and the separator
+    /// used to join multi-select values. Returns `None` for unsupported filters.
+    pub fn split(&self) -> Option<(&'a str, Option<&'a str>)> {
+        match self.expression.split_once('|') {
+            None => Some((self.expression, None)),
+            Some((name, filter)) => filter
+                .trim_start()
+                .strip_prefix("join:")
+                .map(|separator| (name.trim(), Some(separator))),
+        }
+    }
+
+    /// The name of the referenced variable.
+    pub fn name(&self) -> &'a str {
+        match self.expression.split_once('|') {
+            None => self.expression,
+            Some((name, _)) => name.trim(),
+        }
+    }
+}
+
+pub fn find_placeholders(input: &str) -> Vec<Placeholder<'_>> {
+    let mut placeholders = Vec::new();
+    let bytes = input.as_bytes();
+
+    let mut idx = 0;
+    while idx + 1 < bytes.len() {
+        if bytes[idx..].starts_with(b"{{") {
+            if let Some(end) = input[idx + 2..].find("}}") {
+                let next_idx = idx + 2 + end + 2;
+                placeholders.push(Placeholder {
+                    range: idx..next_idx,
+                    expression: &input[idx + 2..idx + 2 + end],
+                });
+                idx = next_idx;
+                continue;
+            }
+        }
+        idx += 1;
+    }
+
+    placeholders
+}
@@ -1,4 +1,9 @@
+use crate::parser::{CommandOutput, OnFailure};
+
 pub trait CommandTrait {
     fn command(&self) -> &str;
     fn name(&self) -> &str;
+    fn on_failure(&self) -> Option<OnFailure>;
+    fn output(&self) -> CommandOutput;
+    fn stdin(&self) -> bool;
 }
@@ -0,0 +1,5 @@
+/// Items of a configuration list that can be overridden by `extends` and `include`.
+pub trait MergeTrait {
+    /// Items with the same key replace each other; items without a key are always appended.
+    fn merge_key(&self) -> Option<&str>;
+}
@@ -1,4 +1,6 @@
 pub mod command;
 pub mod condition;
+pub mod merge;
 
 pub use command::CommandTrait;
+pub use merge::MergeTrait;
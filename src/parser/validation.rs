@@ -0,0 +1,619 @@
+use crate::parser::error::{ParseResult, ValidationIssue};
+use crate::parser::models::condition::Comparable;
+use crate::parser::models::VariableType;
+use crate::parser::placeholder::find_placeholders;
+use crate::parser::traits::condition::ConditionTrait;
+use crate::parser::traits::CommandTrait;
+use crate::parser::{
+    Condition, ConditionOperator, Configuration, ConfigurationDocument, EnvVar, ParserError,
+    TemplateFile, Variable, VariableCondition, VariableValue,
+};
+use regex::Regex;
+
+/// Variables that are added by dynaplate itself and can be referenced without declaring them.
+pub const BUILTIN_VARIABLES: [&str; 2] = ["working_dir", "config_dir"];
+
+/// Validates the whole configuration, reporting every problem found instead of only the first.
+/// Every `{{placeholder}}` in commands, template paths and environment values has to refer to a
+/// declared or built-in variable, so typos are caught before anything is executed. `issues` are
+/// the problems already found in the documents the configuration is composed from.
+pub fn validate_configuration(
+    configuration: &Configuration,
+    mut issues: Vec<ValidationIssue>,
+) -> ParseResult<()> {
+    validate_variables(&configuration.variables, &mut issues);
+
+    let declared: Vec<&str> = BUILTIN_VARIABLES
+        .iter()
+        .copied()
+        .chain(configuration.variables.iter().map(|v| v.name.as_str()))
+        .collect();
+
+    validate_environment(&configuration.environment, &declared, &mut issues);
+    validate_template_files(
+        &configuration.template_files,
+        &declared,
+        &configuration.variables,
+        &mut issues,
+    );
+    validate_commands(
+        "pre_commands",
+        &configuration.pre_commands,
+        &declared,
+        &configuration.variables,
+        &mut issues,
+    );
+    validate_commands(
+        "dependencies",
+        &configuration.dependencies,
+        &declared,
+        &configuration.variables,
+        &mut issues,
+    );
+    validate_commands(
+        "post_commands",
+        &configuration.post_commands,
+        &declared,
+        &configuration.variables,
+        &mut issues,
+    );
+
+    match issues.is_empty() {
+        true => Ok(()),
+        false => Err(ParserError::InvalidConfiguration(issues)),
+    }
+}
+
+/// Validates a single document before it is composed with the documents it extends and includes.
+/// Variables with the same name replace each other across documents, so duplicates within one
+/// document are only visible here. Issues of other documents than the root are prefixed with their
+/// `location`.
+pub fn validate_document(
+    document: &ConfigurationDocument,
+    location: Option<&str>,
+    issues: &mut Vec<ValidationIssue>,
+) {
+    let mut document_issues = Vec::new();
+    validate_duplicate_variables(&document.variables, &mut document_issues);
+    issues.extend(document_issues.into_iter().map(|issue| match location {
+        Some(location) => {
+            ValidationIssue::new(format!("{}: {}", location, issue.path), issue.error)
+        }
+        None => issue,
+    }));
+}
+
+fn validate_duplicate_variables(variables: &[Variable], issues: &mut Vec<ValidationIssue>) {
+    for (idx, variable) in variables.iter().enumerate() {
+        let is_duplicate = BUILTIN_VARIABLES.contains(&variable.name.as_str())
+            || variables[..idx].iter().any(|v| v.name == variable.name);
+        if is_duplicate {
+            issues.push(ValidationIssue::new(
+                format!("variables[{}].name", idx),
+                ParserError::DuplicateVariable(variable.name.clone()),
+            ));
+        }
+    }
+}
+
+fn validate_variables(variables: &[Variable], issues: &mut Vec<ValidationIssue>) {
+    for (idx, variable) in variables.iter().enumerate() {
+        let path = format!("variables[{}]", idx);
+
+        let has_options = matches!(
+            variable.var_type,
+            VariableType::Select | VariableType::MultiSelect
+        );
+        if has_options && variable.options.as_ref().is_none_or(|o| o.is_empty()) {
+            issues.push(ValidationIssue::new(
+                format!("{}.options", path),
+                ParserError::InvalidDefaultValue(format!(
+                    "{} variable '{}' has no options defined.",
+                    variable.var_type.name(),
+                    variable.name
+                )),
+            ));
+        }
+
+        if let Some(error) = validate_range(variable) {
+            issues.push(ValidationIssue::new(format!("{}.min", path), error));
+        }
+
+        if let Some((field, error)) = validate_text_rules(variable) {
+            issues.push(ValidationIssue::new(format!("{}.{}", path, field), error));
+        }
+
+        if let Some(default_value) = &variable.default {
+            if let Some(error) = validate_default(variable, default_value) {
+                issues.push(ValidationIssue::new(format!("{}.default", path), error));
+            }
+        }
+
+        if let Some(value) = &variable.value {
+            if let Err(error) = validate_value(variable, value) {
+                issues.push(ValidationIssue::new(format!("{}.value", path), error));
+            }
+        }
+
+        if let Some(conditions) = variable.get_conditions() {
+            validate_variable_conditions(&path, variables, idx, conditions, issues);
+        }
+    }
+}
+
+/// Variables are gathered in order, so the conditions of a variable can only refer to the
+/// variables declared before it.
+fn validate_variable_conditions(
+    path: &str,
+    variables: &[Variable],
+    idx: usize,
+    conditions: &[Condition],
+    issues: &mut Vec<ValidationIssue>,
+) {
+    let mut condition_issues = Vec::new();
+    for (condition_idx, condition) in conditions.iter().enumerate() {
+        validate_condition(
+            &format!("{}.conditions[{}]", path, condition_idx),
+            condition,
+            &variables[..idx],
+            &mut condition_issues,
+        );
+    }
+
+    issues.extend(condition_issues.into_iter().map(|issue| {
+        match issue.error {
+            ParserError::VariableDoesNotExist(name)
+                if variables[idx..]
+                    .iter()
+                    .any(|v| v.name.to_lowercase() == name.to_lowercase()) =>
+            {
+                ValidationIssue::new(
+                    issue.path,
+                    ParserError::ConditionOnLaterVariable {
+                        variable: variables[idx].name.clone(),
+                        name,
+                    },
+                )
+            }
+            _ => issue,
+        }
+    }));
+}
+
+fn validate_default(variable: &Variable, default_value: &str) -> Option<ParserError> {
+    let options = variable.options.clone().unwrap_or_default();
+
+    match variable.var_type {
+        VariableType::String => variable.check_text(default_value).err(),
+        VariableType::Boolean => (default_value != "true" && default_value != "false").then(|| {
+            ParserError::InvalidDefaultValue(format!(
+                "Invalid default value '{}' for Boolean variable '{}'. Expected 'true' or 'false'.",
+                default_value, variable.name
+            ))
+        }),
+        VariableType::Select => (!options.iter().any(|o| o == default_value)).then(|| {
+            ParserError::InvalidDefaultValue(format!(
+                "Invalid default value '{}' for Select variable '{}'. Expected one of: {:?}.",
+                default_value, variable.name, options
+            ))
+        }),
+        VariableType::MultiSelect => variable
+            .default_selection()
+            .into_iter()
+            .find(|option| !options.contains(option))
+            .map(|option| {
+                ParserError::InvalidDefaultValue(format!(
+                    "Invalid default value '{}' for MultiSelect variable '{}'. Expected any of: {:?}.",
+                    option, variable.name, options
+                ))
+            }),
+        VariableType::Integer | VariableType::Number => {
+            let expected = match (&variable.var_type, variable.min.or(variable.max)) {
+                (VariableType::Integer, None) => "a whole number".to_string(),
+                (VariableType::Integer, Some(_)) => {
+                    format!("a whole number {}", variable.range_description())
+                }
+                (_, None) => "a number".to_string(),
+                (_, Some(_)) => format!("a number {}", variable.range_description()),
+            };
+            variable.parse_value(default_value).err().map(|_| {
+                ParserError::InvalidDefaultValue(format!(
+                    "Invalid default value '{}' for {} variable '{}'. Expected {}.",
+                    default_value,
+                    variable.var_type.name(),
+                    variable.name,
+                    expected
+                ))
+            })
+        }
+    }
+}
+
+/// `pattern`, `min_length`, `max_length` and `not_empty` only apply to string variables, and the
+/// pattern has to compile. Returns the offending field with the problem.
+fn validate_text_rules(variable: &Variable) -> Option<(&'static str, ParserError)> {
+    let has_rules = variable.pattern.is_some()
+        || variable.min_length.is_some()
+        || variable.max_length.is_some()
+        || variable.not_empty;
+    if !has_rules {
+        return None;
+    }
+    if variable.var_type != VariableType::String {
+        return Some((
+            "type",
+            ParserError::InvalidValidationRule(format!(
+                "{} variable '{}' cannot have 'pattern', 'min_length', 'max_length' or 'not_empty'",
+                variable.var_type.name(),
+                variable.name
+            )),
+        ));
+    }
+    if let (Some(min_length), Some(max_length)) = (variable.min_length, variable.max_length) {
+        if min_length > max_length {
+            return Some((
+                "min_length",
+                ParserError::InvalidValidationRule(format!(
+                    "'min_length' of variable '{}' is larger than its 'max_length' ({} > {})",
+                    variable.name, min_length, max_length
+                )),
+            ));
+        }
+    }
+    let pattern = variable.pattern.as_deref()?;
+    Regex::new(pattern).err().map(|e| {
+        (
+            "pattern",
+            ParserError::InvalidValidationRule(format!(
+                "invalid pattern '{}' of variable '{}': {}",
+                pattern,
+                variable.name,
+                regex_error_reason(&e)
+            )),
+        )
+    })
+}
+
+/// `min` and `max` only apply to integer and number variables, and have to form a range.
+fn validate_range(variable: &Variable) -> Option<ParserError> {
+    if variable.min.is_none() && variable.max.is_none() {
+        return None;
+    }
+    if !variable.var_type.is_numeric() {
+        return Some(ParserError::InvalidRange(format!(
+            "{} variable '{}' cannot have 'min' or 'max'",
+            variable.var_type.name(),
+            variable.name
+        )));
+    }
+    match (variable.min, variable.max) {
+        (Some(min), Some(max)) if min > max => Some(ParserError::InvalidRange(format!(
+            "'min' of variable '{}' is larger than its 'max' ({} > {})",
+            variable.name, min, max
+        ))),
+        _ => None,
+    }
+}
+
+/// Checks that a value set in the configuration matches the type of its variable.
+fn validate_value(variable: &Variable, value: &VariableValue) -> ParseResult<()> {
+    match (&variable.var_type, value) {
+        (VariableType::String, VariableValue::String(s)) => variable.check_text(s),
+        (VariableType::Boolean, VariableValue::Boolean(_)) => Ok(()),
+        (VariableType::Select, VariableValue::String(s) | VariableValue::Select(s)) => {
+            variable.parse_value(s).map(|_| ())
+        }
+        (VariableType::MultiSelect, VariableValue::MultiSelect(selection)) => {
+            variable.parse_selection(selection.clone()).map(|_| ())
+        }
+        (VariableType::Integer, VariableValue::Integer(i))
+        | (VariableType::Number, VariableValue::Integer(i)) => variable.check_range(*i as f64),
+        (VariableType::Number, VariableValue::Number(n)) => variable.check_range(*n),
+        _ => Err(ParserError::VariableHasIncorrectValue {
+            name: variable.name.clone(),
+            val: value.to_string(),
+        }),
+    }
+}
+
+fn validate_environment(
+    environment: &[EnvVar],
+    declared: &[&str],
+    issues: &mut Vec<ValidationIssue>,
+) {
+    for (idx, env_var) in environment.iter().enumerate() {
+        validate_placeholders(
+            &format!("environment[{}].value", idx),
+            &env_var.value,
+            declared,
+            issues,
+        );
+    }
+}
+
+fn validate_template_files(
+    files: &[TemplateFile],
+    declared: &[&str],
+    variables: &[Variable],
+    issues: &mut Vec<ValidationIssue>,
+) {
+    for (idx, file) in files.iter().enumerate() {
+        let path = format!("template_files[{}]", idx);
+        validate_placeholders(
+            &format!("{}.source", path),
+            &file.source.display().to_string(),
+            declared,
+            issues,
+        );
+        validate_placeholders(
+            &format!("{}.destination", path),
+            &file.destination.display().to_string(),
+            declared,
+            issues,
+        );
+
+        if let Some(conditions) = file.get_conditions() {
+            for (condition_idx, condition) in conditions.iter().enumerate() {
+                validate_condition(
+                    &format!("{}.conditions[{}]", path, condition_idx),
+                    condition,
+                    variables,
+                    issues,
+                );
+            }
+        }
+    }
+}
+
+fn validate_commands<T: CommandTrait + ConditionTrait>(
+    list_name: &str,
+    commands: &[T],
+    declared: &[&str],
+    variables: &[Variable],
+    issues: &mut Vec<ValidationIssue>,
+) {
+    for (idx, command) in commands.iter().enumerate() {
+        let path = format!("{}[{}]", list_name, idx);
+
+        if command.command().is_empty() {
+            issues.push(ValidationIssue::new(
+                format!("{}.command", path),
+                ParserError::CommandIsEmpty {
+                    name: command.name().to_string(),
+                },
+            ));
+        }
+
+        validate_placeholders(
+            &format!("{}.command", path),
+            command.command(),
+            declared,
+            issues,
+        );
+
+        if let Some(conditions) = command.get_conditions() {
+            for (condition_idx, condition) in conditions.iter().enumerate() {
+                validate_condition(
+                    &format!("{}.conditions[{}]", path, condition_idx),
+                    condition,
+                    variables,
+                    issues,
+                );
+            }
+        }
+    }
+}
+
+fn validate_condition(
+    path: &str,
+    condition: &Condition,
+    variables: &[Variable],
+    issues: &mut Vec<ValidationIssue>,
+) {
+    match condition {
+        Condition::All { all } => {
+            for (idx, condition) in all.iter().enumerate() {
+                validate_condition(
+                    &format!("{}.all[{}]", path, idx),
+                    condition,
+                    variables,
+                    issues,
+                );
+            }
+        }
+        Condition::Any { any } => {
+            for (idx, condition) in any.iter().enumerate() {
+                validate_condition(
+                    &format!("{}.any[{}]", path, idx),
+                    condition,
+                    variables,
+                    issues,
+                );
+            }
+        }
+        Condition::Not { not } => {
+            validate_condition(&format!("{}.not", path), not, variables, issues)
+        }
+        Condition::Variable(condition) => {
+            // conditions match variable names case-insensitively
+            let name = condition.variable.to_lowercase();
+            if BUILTIN_VARIABLES.iter().any(|builtin| *builtin == name) {
+                validate_variable_condition(path, condition, &VariableType::String, &[], issues);
+            } else if let Some(variable) = variables.iter().find(|v| v.name.to_lowercase() == name)
+            {
+                validate_variable_condition(
+                    path,
+                    condition,
+                    &variable.var_type,
+                    variable.options.as_deref().unwrap_or_default(),
+                    issues,
+                );
+            } else {
+                issues.push(ValidationIssue::new(
+                    format!("{}.variable", path),
+                    ParserError::VariableDoesNotExist(condition.variable.clone()),
+                ));
+            }
+        }
+    }
+}
+
+/// Checks that the operator suits the type of the variable and that the value can be compared
+/// with it, e.g. that a pattern compiles or that a select value is one of the options.
+fn validate_variable_condition(
+    path: &str,
+    condition: &VariableCondition,
+    var_type: &VariableType,
+    options: &[String],
+    issues: &mut Vec<ValidationIssue>,
+) {
+    let operator = condition.operator;
+    let supported = match var_type {
+        VariableType::Boolean => matches!(
+            operator,
+            ConditionOperator::Equals
+                | ConditionOperator::NotEquals
+                | ConditionOperator::IsSet
+                | ConditionOperator::IsEmpty
+        ),
+        VariableType::MultiSelect => !matches!(
+            operator,
+            ConditionOperator::GreaterThan | ConditionOperator::LessThan
+        ),
+        VariableType::Integer | VariableType::Number => matches!(
+            operator,
+            ConditionOperator::Equals
+                | ConditionOperator::NotEquals
+                | ConditionOperator::In
+                | ConditionOperator::NotIn
+                | ConditionOperator::GreaterThan
+                | ConditionOperator::LessThan
+                | ConditionOperator::IsSet
+                | ConditionOperator::IsEmpty
+        ),
+        VariableType::String | VariableType::Select => true,
+    };
+    if !supported {
+        issues.push(ValidationIssue::new(
+            format!("{}.operator", path),
+            ParserError::InvalidCondition(format!(
+                "operator {} cannot be used with {} variable '{}'",
+                operator,
+                var_type.name(),
+                condition.variable
+            )),
+        ));
+        return;
+    }
+
+    let error = match (&condition.value, operator.takes_value()) {
+        (None, true) => Some(format!("operator {} requires a value", operator)),
+        (Some(_), false) => Some(format!("operator {} does not take a value", operator)),
+        (None, false) => None,
+        (Some(value), true) => validate_condition_value(operator, var_type, options, value).err(),
+    };
+    if let Some(error) = error {
+        issues.push(ValidationIssue::new(
+            format!("{}.value", path),
+            ParserError::InvalidCondition(format!("'{}': {}", condition.variable, error)),
+        ));
+    }
+}
+
+fn validate_condition_value(
+    operator: ConditionOperator,
+    var_type: &VariableType,
+    options: &[String],
+    value: &VariableValue,
+) -> Result<(), String> {
+    let texts: Vec<String> = match value {
+        VariableValue::Boolean(_) if *var_type == VariableType::Boolean => return Ok(()),
+        VariableValue::Boolean(_) => {
+            return Err(format!(
+                "{} cannot be compared with a boolean",
+                var_type.name()
+            ))
+        }
+        _ if *var_type == VariableType::Boolean => {
+            return Err(format!("expected true or false, got {}", value))
+        }
+        VariableValue::String(s) | VariableValue::Select(s) => vec![s.clone()],
+        VariableValue::MultiSelect(list) => list.clone(),
+        VariableValue::Integer(i) => vec![i.to_string()],
+        VariableValue::Number(n) => vec![n.to_string()],
+    };
+
+    let single_text = || match value {
+        VariableValue::MultiSelect(_) => {
+            Err(format!("operator {} expects a single value", operator))
+        }
+        _ => Ok(texts[0].as_str()),
+    };
+
+    if var_type.is_numeric() {
+        if let Some(text) = texts.iter().find(|t| t.trim().parse::<f64>().is_err()) {
+            return Err(format!("'{}' is not a number", text));
+        }
+        return match operator {
+            ConditionOperator::In | ConditionOperator::NotIn => Ok(()),
+            _ => single_text().map(|_| ()),
+        };
+    }
+
+    match operator {
+        ConditionOperator::Matches => {
+            let pattern = single_text()?;
+            Regex::new(pattern)
+                .map(|_| ())
+                .map_err(|e| format!("invalid pattern '{}': {}", pattern, regex_error_reason(&e)))
+        }
+        ConditionOperator::StartsWith | ConditionOperator::EndsWith => single_text().map(|_| ()),
+        ConditionOperator::GreaterThan | ConditionOperator::LessThan => {
+            let text = single_text()?;
+            match Comparable::parse(text) {
+                Some(_) => Ok(()),
+                None => Err(format!("'{}' is neither a number nor a version", text)),
+            }
+        }
+        // a select can also be searched for text, so only a multiselect's options are checked
+        ConditionOperator::Contains if *var_type == VariableType::Select => Ok(()),
+        _ if matches!(var_type, VariableType::Select | VariableType::MultiSelect) => texts
+            .into_iter()
+            .find(|text| !options.iter().any(|option| option == text))
+            .map_or(Ok(()), |text| {
+                Err(format!(
+                    "'{}' is not one of the options {:?}",
+                    text, options
+                ))
+            }),
+        _ => Ok(()),
+    }
+}
+
+/// The syntax error of a pattern spans several lines; its last line is the actual reason.
+fn regex_error_reason(error: &regex::Error) -> String {
+    let error = error.to_string();
+    let reason = error.lines().last().unwrap_or_default();
+    reason.trim_start_matches("error: ").to_string()
+}
+
+fn validate_placeholders(
+    path: &str,
+    input: &str,
+    declared: &[&str],
+    issues: &mut Vec<ValidationIssue>,
+) {
+    for placeholder in find_placeholders(input) {
+        if placeholder.split().is_none() {
+            issues.push(ValidationIssue::new(
+                path.to_string(),
+                ParserError::InvalidPlaceholder(placeholder.expression.to_string()),
+            ));
+        } else if !declared.contains(&placeholder.name()) {
+            issues.push(ValidationIssue::new(
+                path.to_string(),
+                ParserError::VariableDoesNotExist(placeholder.name().to_string()),
+            ));
+        }
+    }
+}
@@ -1,23 +1,40 @@
 use crate::parser::traits::condition::ConditionTrait;
 use crate::parser::traits::CommandTrait;
-use crate::parser::Variable;
-use crate::utils::condition::has_applicable_conditions;
+use crate::parser::{CommandOutput, OnConflict, OnFailure, Variable};
+use crate::utils::condition::evaluate_conditions;
 use crate::utils::error::UtilsResult;
 use crate::utils::variable::replace_variables;
 use crate::utils::UtilsError;
 use crate::utils::UtilsError::{CommandFailedDueToParseError, CommandNotApplicable};
 use std::collections::HashMap;
-use std::io::{Error, ErrorKind};
+use std::io::{BufRead, BufReader, Error, Read, Write};
 use std::path::PathBuf;
+use std::process::{ExitStatus, Stdio};
+use std::{io, thread};
 
 pub struct Project {
     pub working_dir: PathBuf,
     pub envs: HashMap<String, String>,
     pub variables: Vec<Variable>,
     pub clean: bool,
+    pub fail_fast: bool,
+    pub on_conflict: OnConflict,
+    /// Never prompt, so conflicts with the `prompt` policy fail.
+    pub no_input: bool,
 }
 
-pub fn execute_commands<T: CommandTrait + ConditionTrait>(commands: &[T], project: &Project) {
+pub struct CommandFailure {
+    pub name: String,
+    pub reason: String,
+}
+
+/// Executes the commands in order, recording every failed command in `failures`. Returns an error
+/// as soon as a command with the `abort` policy fails; `--fail-fast` makes `abort` the default.
+pub fn execute_commands<T: CommandTrait + ConditionTrait>(
+    commands: &[T],
+    project: &Project,
+    failures: &mut Vec<CommandFailure>,
+) -> UtilsResult<()> {
     for command in commands {
         println!("Processing command '{}'", command.name());
         match execute_command(command, project) {
@@ -25,50 +42,103 @@ pub fn execute_commands<T: CommandTrait + ConditionTrait>(commands: &[T], projec
                 println!("Successfully processed command: {}", command.name());
             }
             Err(e) => match e {
-                CommandNotApplicable { .. } => {
-                    println!("Skipped command: {} (false condition)", command.name());
+                CommandNotApplicable { reason, .. } => {
+                    println!(
+                        "Skipped command: {} (false condition: {})",
+                        command.name(),
+                        reason
+                    );
                 }
                 e => {
+                    let policy = command.on_failure().unwrap_or(match project.fail_fast {
+                        true => OnFailure::Abort,
+                        false => OnFailure::Continue,
+                    });
+
+                    if policy == OnFailure::Warn {
+                        eprintln!("Warning: command '{}' failed: {}", command.name(), e);
+                        continue;
+                    }
+
                     eprintln!("Failed executing command '{}': {}", command.name(), e);
+                    failures.push(CommandFailure {
+                        name: command.name().to_string(),
+                        reason: e.to_string(),
+                    });
+
+                    if policy == OnFailure::Abort {
+                        return Err(UtilsError::CommandAborted {
+                            name: command.name().to_string(),
+                        });
+                    }
                 }
             },
         };
     }
+    Ok(())
 }
 
-pub fn execute_command<T: CommandTrait + ConditionTrait>(
+/// Prints what `execute_commands` would do, without spawning any process.
+pub fn plan_commands<T: CommandTrait + ConditionTrait>(commands: &[T], project: &Project) {
+    for command in commands {
+        match resolve_command(command, project) {
+            Ok(cmd) => println!("  run   {}: {}", command.name(), cmd),
+            Err(CommandNotApplicable { reason, .. }) => {
+                println!("  skip  {} (false condition: {})", command.name(), reason)
+            }
+            Err(e) => println!("  error {}: {}", command.name(), e),
+        }
+    }
+}
+
+/// Evaluates the conditions of a command and returns the command string with all variables
+/// replaced, or `CommandNotApplicable` when its conditions do not hold.
+fn resolve_command<T: CommandTrait + ConditionTrait>(
     command: &T,
     project: &Project,
-) -> UtilsResult<()> {
+) -> UtilsResult<String> {
     if command.get_conditions().is_some() {
-        match has_applicable_conditions(command.get_conditions(), &project.variables) {
-            Ok(condition) => {
-                if !condition {
-                    return Err(CommandNotApplicable {
-                        name: command.name().to_string(),
-                    });
-                }
+        match evaluate_conditions(command.get_conditions(), &project.variables) {
+            Ok(None) => {}
+            Ok(Some(reason)) => {
+                return Err(CommandNotApplicable {
+                    name: command.name().to_string(),
+                    reason,
+                });
             }
             Err(e) => return Err(CommandFailedDueToParseError(e)),
         }
     }
 
-    let cmd_with_variables_replaced =
-        replace_variables(command.command(), &project.variables, &project.clean)?;
+    replace_variables(command.command(), &project.variables, &project.clean)
+}
+
+pub fn execute_command<T: CommandTrait + ConditionTrait>(
+    command: &T,
+    project: &Project,
+) -> UtilsResult<()> {
+    let cmd_with_variables_replaced = resolve_command(command, project)?;
 
     let mut cmd = std::process::Command::new("sh");
     cmd.arg("-c").arg(cmd_with_variables_replaced);
 
     cmd.current_dir(&project.working_dir).envs(&project.envs);
+    cmd.stdin(match command.stdin() {
+        true => Stdio::inherit(),
+        false => Stdio::null(),
+    });
 
-    match cmd.output() {
-        Ok(output) => match output.status.success() {
+    match run_process(&mut cmd, command.name(), command.output()) {
+        Ok((status, stderr)) => match status.success() {
             true => Ok(()),
             false => {
-                let error_message = String::from_utf8_lossy(&output.stderr);
+                let error_message = match stderr.trim() {
+                    "" => status.to_string(),
+                    message => message.to_string(),
+                };
                 Err(UtilsError::CommandFailed {
                     name: command.name().to_string(),
-                    source: Error::new(ErrorKind::Other, error_message.to_string()),
+                    source: Error::other(error_message),
                 })
             }
         },
@@ -78,3 +148,83 @@ pub fn execute_command<T: CommandTrait + ConditionTrait>(
         }),
     }
 }
+
+/// Runs the process with the requested output handling and returns its exit status together with
+/// whatever it wrote to stderr (empty when the output is inherited).
+fn run_process(
+    cmd: &mut std::process::Command,
+    name: &str,
+    output: CommandOutput,
+) -> io::Result<(ExitStatus, String)> {
+    match output {
+        CommandOutput::Inherit => {
+            let status = cmd
+                .stdout(Stdio::inherit())
+                .stderr(Stdio::inherit())
+                .status()?;
+            Ok((status, String::new()))
+        }
+        CommandOutput::Stream => {
+            let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+            let stdout = child.stdout.take();
+            let stderr = child.stderr.take();
+
+            let stderr = thread::scope(|scope| {
+                if let Some(stdout) = stdout {
+                    scope.spawn(move || {
+                        for_each_line(stdout, |line| println!("[{}] {}", name, line));
+                    });
+                }
+
+                let mut collected = String::new();
+                if let Some(stderr) = stderr {
+                    for_each_line(stderr, |line| {
+                        eprintln!("[{}] {}", name, line);
+                        collected.push_str(line);
+                        collected.push('\n');
+                    });
+                }
+                collected
+            });
+
+            Ok((child.wait()?, stderr))
+        }
+        CommandOutput::Capture => {
+            let output = cmd.output()?;
+            io::stdout().write_all(&output.stdout)?;
+            io::stderr().write_all(&output.stderr)?;
+            Ok((
+                output.status,
+                String::from_utf8_lossy(&output.stderr).to_string(),
+            ))
+        }
+        CommandOutput::Silent => {
+            let output = cmd.stdout(Stdio::null()).output()?;
+            Ok((
+                output.status,
+                String::from_utf8_lossy(&output.stderr).to_string(),
+            ))
+        }
+    }
+}
+
+/// Calls `handle` for every line read from `reader` until EOF. Output that is not valid UTF-8 is
+/// converted lossily instead of ending the loop, so the pipe is always drained and the process
+/// never gets a SIGPIPE.
+fn for_each_line(reader: impl Read, mut handle: impl FnMut(&str)) {
+    let mut reader = BufReader::new(reader);
+    let mut buffer = Vec::new();
+    loop {
+        buffer.clear();
+        match reader.read_until(b'\n', &mut buffer) {
+            Ok(0) => break,
+            Ok(_) => {
+                let line = String::from_utf8_lossy(&buffer);
+                let line = line.strip_suffix('\n').unwrap_or(&line);
+                handle(line.strip_suffix('\r').unwrap_or(line));
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => break,
+        }
+    }
+}
@@ -1,36 +1,480 @@
 use crate::parser::error::ParseResult;
-use crate::parser::{Condition, ConditionOperator, ParserError, Variable, VariableValue};
+use crate::parser::models::condition::Comparable;
+use crate::parser::{
+    Condition, ConditionOperator, ParserError, Variable, VariableCondition, VariableValue,
+};
+use regex::Regex;
+use std::cmp::Ordering;
 
-pub fn has_applicable_conditions(
+/// Evaluates a list of conditions, of which at least one has to hold (for backward compatibility
+/// a flat list behaves like an `any` group). Returns `None` when the conditions hold, or the
+/// reason why they do not.
+pub fn evaluate_conditions(
     conditions: Option<&[Condition]>,
     variables: &[Variable],
-) -> ParseResult<bool> {
-    if let Some(conditions) = conditions {
-        for condition in conditions {
-            let var = variables
+) -> ParseResult<Option<String>> {
+    match conditions {
+        Some(conditions) => evaluate_any(conditions, variables, "conditions"),
+        None => Ok(Some("no conditions specified".to_string())),
+    }
+}
+
+fn evaluate_condition(
+    condition: &Condition,
+    variables: &[Variable],
+    path: &str,
+) -> ParseResult<Option<String>> {
+    match condition {
+        Condition::All { all } => {
+            for (idx, condition) in all.iter().enumerate() {
+                let reason =
+                    evaluate_condition(condition, variables, &format!("{}.all[{}]", path, idx))?;
+                if reason.is_some() {
+                    return Ok(reason);
+                }
+            }
+            Ok(None)
+        }
+        Condition::Any { any } => evaluate_any(any, variables, &format!("{}.any", path)),
+        Condition::Not { not } => {
+            let not_path = format!("{}.not", path);
+            match evaluate_condition(not, variables, &not_path)? {
+                Some(_) => Ok(None),
+                None => Ok(Some(format!("{}: negated condition holds", not_path))),
+            }
+        }
+        Condition::Variable(condition) => evaluate_variable_condition(condition, variables, path),
+    }
+}
+
+fn evaluate_any(
+    conditions: &[Condition],
+    variables: &[Variable],
+    path: &str,
+) -> ParseResult<Option<String>> {
+    if conditions.is_empty() {
+        return Ok(Some(format!("{}: no conditions to match", path)));
+    }
+
+    let mut reasons = Vec::with_capacity(conditions.len());
+    for (idx, condition) in conditions.iter().enumerate() {
+        match evaluate_condition(condition, variables, &format!("{}[{}]", path, idx))? {
+            Some(reason) => reasons.push(reason),
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(reasons.join("; ")))
+}
+
+fn evaluate_variable_condition(
+    condition: &VariableCondition,
+    variables: &[Variable],
+    path: &str,
+) -> ParseResult<Option<String>> {
+    let var = variables
+        .iter()
+        .find(|var| var.name.to_lowercase() == condition.variable.to_lowercase())
+        .ok_or_else(|| ParserError::VariableDoesNotExist(condition.variable.clone()))?;
+
+    let condition_applicable = match (&condition.operator, &var.value, &condition.value) {
+        (ConditionOperator::IsSet, value, _) => value.as_ref().is_some_and(|v| !is_empty(v)),
+        (ConditionOperator::IsEmpty, value, _) => value.as_ref().is_none_or(is_empty),
+        (operator, Some(value), Some(expected)) => value_matches(operator, value, expected),
+        _ => false,
+    };
+
+    if condition_applicable {
+        return Ok(None);
+    }
+
+    let actual = match &var.value {
+        Some(value) => value.to_string(),
+        None => "unset".to_string(),
+    };
+    Ok(Some(format!(
+        "{}: {} (actual: {})",
+        path,
+        condition.describe(),
+        actual
+    )))
+}
+
+fn value_matches(
+    operator: &ConditionOperator,
+    actual: &VariableValue,
+    expected: &VariableValue,
+) -> bool {
+    match operator {
+        ConditionOperator::Equals => values_equal(actual, expected),
+        ConditionOperator::NotEquals => match (actual, expected) {
+            (VariableValue::Boolean(_), VariableValue::Boolean(_))
+            | (VariableValue::MultiSelect(_), VariableValue::MultiSelect(_)) => {
+                !values_equal(actual, expected)
+            }
+            (VariableValue::Integer(_) | VariableValue::Number(_), _) => {
+                expected.as_number().is_some() && !values_equal(actual, expected)
+            }
+            _ => match (as_text(actual), as_text(expected)) {
+                (Some(v), Some(c)) => v != c,
+                _ => false, // Type mismatch
+            },
+        },
+        ConditionOperator::Contains => match (actual, expected) {
+            (VariableValue::MultiSelect(v), VariableValue::MultiSelect(c)) => {
+                c.iter().all(|option| v.contains(option))
+            }
+            (VariableValue::MultiSelect(v), c) => {
+                as_text(c).is_some_and(|c| v.iter().any(|option| option == c))
+            }
+            _ => match (as_text(actual), as_text(expected)) {
+                (Some(v), Some(c)) => v.contains(c),
+                _ => false, // Type mismatch
+            },
+        },
+        ConditionOperator::In => match (actual, expected) {
+            (VariableValue::MultiSelect(v), VariableValue::MultiSelect(c)) => {
+                v.iter().all(|option| c.contains(option))
+            }
+            (
+                VariableValue::Integer(_) | VariableValue::Number(_),
+                VariableValue::MultiSelect(c),
+            ) => c
                 .iter()
-                .find(|var| var.name.to_lowercase() == condition.variable.to_lowercase())
-                .ok_or_else(|| ParserError::VariableDoesNotExist(condition.variable.clone()))?;
-
-            let condition_applicable = match condition.operator {
-                ConditionOperator::Equals => match (&var.value, &condition.value) {
-                    (Some(VariableValue::String(v)), VariableValue::String(c)) => v == c,
-                    (Some(VariableValue::Boolean(v)), VariableValue::Boolean(c)) => v == c,
-                    (Some(VariableValue::Select(v)), VariableValue::String(c)) => v == c, // TODO: add support for MultipleSelect
-                    _ => false, // Type mismatch or None value
-                },
-                ConditionOperator::NotEquals => match (&var.value, &condition.value) {
-                    (Some(VariableValue::String(v)), VariableValue::String(c)) => v != c,
-                    (Some(VariableValue::Boolean(v)), VariableValue::Boolean(c)) => v != c,
-                    (Some(VariableValue::Select(v)), VariableValue::String(c)) => v != c, // TODO: add support for MultipleSelect
-                    _ => false, // Type mismatch or None value
-                },
-            };
-
-            if condition_applicable {
-                return Ok(true);
-            }
-        }
-    }
-    Ok(false)
+                .any(|option| values_equal(actual, &VariableValue::String(option.clone()))),
+            (v, VariableValue::MultiSelect(c)) => {
+                as_text(v).is_some_and(|v| c.iter().any(|option| option == v))
+            }
+            _ => values_equal(actual, expected),
+        },
+        ConditionOperator::NotIn => !value_matches(&ConditionOperator::In, actual, expected),
+        ConditionOperator::Matches => as_text(expected)
+            .and_then(|pattern| Regex::new(pattern).ok())
+            .is_some_and(|regex| any_text(actual, |text| regex.is_match(text))),
+        ConditionOperator::StartsWith => as_text(expected)
+            .is_some_and(|prefix| any_text(actual, |text| text.starts_with(prefix))),
+        ConditionOperator::EndsWith => {
+            as_text(expected).is_some_and(|suffix| any_text(actual, |text| text.ends_with(suffix)))
+        }
+        ConditionOperator::GreaterThan => compare(actual, expected) == Some(Ordering::Greater),
+        ConditionOperator::LessThan => compare(actual, expected) == Some(Ordering::Less),
+        ConditionOperator::IsSet => !is_empty(actual),
+        ConditionOperator::IsEmpty => is_empty(actual),
+    }
+}
+
+/// Whether any of the texts of a value satisfies the predicate, so a multiselect matches when
+/// one of its selected options does.
+fn any_text(value: &VariableValue, predicate: impl Fn(&str) -> bool) -> bool {
+    match value {
+        VariableValue::MultiSelect(options) => options.iter().any(|option| predicate(option)),
+        _ => as_text(value).is_some_and(predicate),
+    }
+}
+
+/// Orders two values as numbers or semantic versions, `None` when they are not comparable.
+fn compare(actual: &VariableValue, expected: &VariableValue) -> Option<Ordering> {
+    comparable(actual)?.partial_cmp(&comparable(expected)?)
+}
+
+fn comparable(value: &VariableValue) -> Option<Comparable> {
+    match value {
+        VariableValue::Integer(_) | VariableValue::Number(_) => {
+            value.as_number().map(Comparable::Number)
+        }
+        _ => Comparable::parse(as_text(value)?),
+    }
+}
+
+fn is_empty(value: &VariableValue) -> bool {
+    match value {
+        VariableValue::String(s) | VariableValue::Select(s) => s.is_empty(),
+        VariableValue::MultiSelect(options) => options.is_empty(),
+        VariableValue::Boolean(_) | VariableValue::Integer(_) | VariableValue::Number(_) => false,
+    }
+}
+
+fn values_equal(actual: &VariableValue, expected: &VariableValue) -> bool {
+    match (actual, expected) {
+        (VariableValue::Boolean(v), VariableValue::Boolean(c)) => v == c,
+        (VariableValue::MultiSelect(v), VariableValue::MultiSelect(c)) => {
+            v.len() == c.len() && v.iter().all(|option| c.contains(option))
+        }
+        (VariableValue::Integer(_) | VariableValue::Number(_), _)
+        | (_, VariableValue::Integer(_) | VariableValue::Number(_)) => {
+            match (actual.as_number(), expected.as_number()) {
+                (Some(v), Some(c)) => v == c,
+                _ => false, // Type mismatch
+            }
+        }
+        _ => match (as_text(actual), as_text(expected)) {
+            (Some(v), Some(c)) => v == c,
+            _ => false, // Type mismatch
+        },
+    }
+}
+
+/// Strings and (single) selections are compared by their text, integers and numbers by value.
+fn as_text(value: &VariableValue) -> Option<&str> {
+    match value {
+        VariableValue::String(s) | VariableValue::Select(s) => Some(s),
+        VariableValue::Boolean(_)
+        | VariableValue::MultiSelect(_)
+        | VariableValue::Integer(_)
+        | VariableValue::Number(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A variable of the given type with the options `a`, `b` and `c`, set to `raw` as if it was
+    /// entered, or unset.
+    fn variable(var_type: &str, raw: Option<&str>) -> Variable {
+        let mut variable: Variable = serde_yml::from_str(&format!(
+            "{{name: v, description: v, type: {}, options: [a, b, c]}}",
+            var_type
+        ))
+        .unwrap();
+        variable.value = raw.map(|raw| variable.parse_value(raw).unwrap());
+        variable
+    }
+
+    /// Whether a condition on the variable, such as `operator: in, value: [a, b]`, holds.
+    fn holds(variable: &Variable, condition: &str) -> bool {
+        let condition: Condition =
+            serde_yml::from_str(&format!("{{variable: v, {}}}", condition)).unwrap();
+        evaluate_conditions(Some(&[condition]), std::slice::from_ref(variable))
+            .unwrap()
+            .is_none()
+    }
+
+    #[test]
+    fn equals() {
+        let string = variable("string", Some("main"));
+        assert!(holds(&string, "operator: equals, value: main"));
+        assert!(!holds(&string, "operator: equals, value: Main"));
+
+        let boolean = variable("boolean", Some("true"));
+        assert!(holds(&boolean, "operator: equals, value: true"));
+        assert!(!holds(&boolean, "operator: equals, value: false"));
+
+        let select = variable("select", Some("b"));
+        assert!(holds(&select, "operator: equals, value: b"));
+        assert!(!holds(&select, "operator: equals, value: a"));
+
+        // lists are compared as a whole, in any order
+        let multiselect = variable("multiselect", Some("a,b"));
+        assert!(holds(&multiselect, "operator: equals, value: [b, a]"));
+        assert!(!holds(&multiselect, "operator: equals, value: [a]"));
+
+        let integer = variable("integer", Some("8"));
+        assert!(holds(&integer, "operator: equals, value: 8"));
+        assert!(holds(&integer, "operator: equals, value: 8.0"));
+        assert!(!holds(&integer, "operator: equals, value: eight"));
+
+        let number = variable("number", Some("1.5"));
+        assert!(holds(&number, "operator: equals, value: 1.50"));
+        assert!(!holds(&number, "operator: equals, value: 2"));
+    }
+
+    #[test]
+    fn not_equals() {
+        let string = variable("string", Some("main"));
+        assert!(holds(&string, "operator: not_equals, value: dev"));
+        assert!(!holds(&string, "operator: notequals, value: main"));
+
+        let boolean = variable("boolean", Some("true"));
+        assert!(holds(&boolean, "operator: notequals, value: false"));
+        assert!(!holds(&boolean, "operator: not_equals, value: true"));
+
+        let select = variable("select", Some("b"));
+        assert!(holds(&select, "operator: not_equals, value: a"));
+
+        let multiselect = variable("multiselect", Some("a,b"));
+        assert!(holds(&multiselect, "operator: not_equals, value: [a]"));
+        assert!(!holds(&multiselect, "operator: not_equals, value: [b, a]"));
+
+        // a value that is no number never differs from a number
+        let integer = variable("integer", Some("8"));
+        assert!(holds(&integer, "operator: not_equals, value: 3"));
+        assert!(!holds(&integer, "operator: not_equals, value: eight"));
+
+        let number = variable("number", Some("1.5"));
+        assert!(holds(&number, "operator: not_equals, value: 2.5"));
+    }
+
+    #[test]
+    fn contains() {
+        let string = variable("string", Some("frontend"));
+        assert!(holds(&string, "operator: contains, value: front"));
+        assert!(!holds(&string, "operator: contains, value: back"));
+
+        let select = variable("select", Some("b"));
+        assert!(holds(&select, "operator: contains, value: b"));
+
+        let multiselect = variable("multiselect", Some("a,b"));
+        assert!(holds(&multiselect, "operator: contains, value: a"));
+        assert!(holds(&multiselect, "operator: contains, value: [b, a]"));
+        assert!(!holds(&multiselect, "operator: contains, value: [a, c]"));
+
+        let boolean = variable("boolean", Some("true"));
+        assert!(!holds(&boolean, "operator: contains, value: true"));
+    }
+
+    #[test]
+    fn in_and_not_in() {
+        let string = variable("string", Some("main"));
+        assert!(holds(&string, "operator: in, value: [main, dev]"));
+        assert!(!holds(&string, "operator: not_in, value: [main, dev]"));
+
+        let select = variable("select", Some("b"));
+        assert!(holds(&select, "operator: in, value: [a, b]"));
+        assert!(holds(&select, "operator: in, value: b"));
+        assert!(holds(&select, "operator: not_in, value: [c]"));
+        assert!(!holds(&select, "operator: in, value: [c]"));
+
+        // every selected option has to be in the list
+        let multiselect = variable("multiselect", Some("a,b"));
+        assert!(holds(&multiselect, "operator: in, value: [a, b, c]"));
+        assert!(!holds(&multiselect, "operator: in, value: [a]"));
+        assert!(holds(&multiselect, "operator: not_in, value: [a]"));
+
+        let integer = variable("integer", Some("8"));
+        assert!(holds(&integer, "operator: in, value: [8, 9]"));
+        assert!(!holds(&integer, "operator: in, value: [1, 2]"));
+        assert!(holds(&integer, "operator: not_in, value: [1, 2]"));
+
+        let number = variable("number", Some("1.5"));
+        assert!(holds(&number, "operator: in, value: [1.5, 2.5]"));
+
+        let boolean = variable("boolean", Some("true"));
+        assert!(holds(&boolean, "operator: in, value: true"));
+        assert!(holds(&boolean, "operator: not_in, value: false"));
+    }
+
+    #[test]
+    fn matches() {
+        let string = variable("string", Some("feature/login"));
+        assert!(holds(&string, "operator: matches, value: '^feature/'"));
+        assert!(!holds(&string, "operator: matches, value: '^fix/'"));
+        assert!(!holds(&string, "operator: matches, value: '('"));
+
+        let select = variable("select", Some("b"));
+        assert!(holds(&select, "operator: matches, value: '^[ab]$'"));
+
+        let multiselect = variable("multiselect", Some("a,b"));
+        assert!(holds(&multiselect, "operator: matches, value: '^b'"));
+        assert!(!holds(&multiselect, "operator: matches, value: '^c'"));
+
+        let integer = variable("integer", Some("8"));
+        assert!(!holds(&integer, "operator: matches, value: '8'"));
+    }
+
+    #[test]
+    fn starts_with_and_ends_with() {
+        let string = variable("string", Some("my-app"));
+        assert!(holds(&string, "operator: starts_with, value: my-"));
+        assert!(holds(&string, "operator: ends_with, value: -app"));
+        assert!(!holds(&string, "operator: starts_with, value: app"));
+        assert!(!holds(&string, "operator: ends_with, value: my"));
+
+        let select = variable("select", Some("b"));
+        assert!(holds(&select, "operator: starts_with, value: b"));
+
+        let multiselect = variable("multiselect", Some("a,c"));
+        assert!(holds(&multiselect, "operator: ends_with, value: c"));
+        assert!(!holds(&multiselect, "operator: starts_with, value: b"));
+
+        let boolean = variable("boolean", Some("true"));
+        assert!(!holds(&boolean, "operator: starts_with, value: t"));
+    }
+
+    #[test]
+    fn greater_than_and_less_than() {
+        let integer = variable("integer", Some("8"));
+        assert!(holds(&integer, "operator: greater_than, value: 5"));
+        assert!(!holds(&integer, "operator: greater_than, value: 10"));
+        assert!(holds(&integer, "operator: less_than, value: 10"));
+
+        let number = variable("number", Some("1.5"));
+        assert!(holds(&number, "operator: greater_than, value: 1"));
+        assert!(holds(&number, "operator: less_than, value: 1.75"));
+
+        // text is compared as a number, or else as a semantic version
+        let string = variable("string", Some("10"));
+        assert!(holds(&string, "operator: greater_than, value: 9"));
+
+        let version = variable("string", Some("v1.10.0"));
+        assert!(holds(&version, "operator: greater_than, value: 1.9.0"));
+        assert!(holds(&version, "operator: less_than, value: v2.0.0"));
+        assert!(!holds(&version, "operator: greater_than, value: 1"));
+        assert!(!holds(&version, "operator: less_than, value: 1"));
+
+        let boolean = variable("boolean", Some("true"));
+        assert!(!holds(&boolean, "operator: greater_than, value: 0"));
+
+        let multiselect = variable("multiselect", Some("a"));
+        assert!(!holds(&multiselect, "operator: less_than, value: 1"));
+    }
+
+    #[test]
+    fn is_set_and_is_empty() {
+        let string = variable("string", Some(""));
+        assert!(holds(&string, "operator: is_empty"));
+        assert!(!holds(&string, "operator: is_set"));
+        assert!(holds(&variable("string", Some("x")), "operator: is_set"));
+
+        let multiselect = variable("multiselect", Some(""));
+        assert!(holds(&multiselect, "operator: is_empty"));
+        assert!(holds(
+            &variable("multiselect", Some("a")),
+            "operator: is_set"
+        ));
+
+        // values of other types are never empty
+        assert!(holds(
+            &variable("boolean", Some("false")),
+            "operator: is_set"
+        ));
+        assert!(holds(&variable("integer", Some("0")), "operator: is_set"));
+        assert!(!holds(&variable("number", Some("0")), "operator: is_empty"));
+        assert!(holds(&variable("select", Some("a")), "operator: is_set"));
+    }
+
+    #[test]
+    fn unset_variables_only_match_is_empty() {
+        for var_type in [
+            "string",
+            "boolean",
+            "select",
+            "multiselect",
+            "integer",
+            "number",
+        ] {
+            let unset = variable(var_type, None);
+            assert!(holds(&unset, "operator: is_empty"));
+            assert!(!holds(&unset, "operator: is_set"));
+            assert!(!holds(&unset, "operator: equals, value: a"));
+            assert!(!holds(&unset, "operator: not_equals, value: a"));
+            assert!(!holds(&unset, "operator: in, value: [a]"));
+            assert!(!holds(&unset, "operator: not_in, value: [a]"));
+            assert!(!holds(&unset, "operator: greater_than, value: 1"));
+        }
+    }
+
+    #[test]
+    fn groups() {
+        let string = variable("string", Some("main"));
+        let all =
+            "all: [{variable: v, operator: is_set}, {variable: v, operator: equals, value: dev}]";
+        let any = "any: [{variable: v, operator: is_empty}, {variable: v, operator: equals, value: main}]";
+        let not = "not: {variable: v, operator: equals, value: dev}";
+        for (group, expected) in [(all, false), (any, true), (not, true)] {
+            let condition: Condition = serde_yml::from_str(&format!("{{{}}}", group)).unwrap();
+            let reason =
+                evaluate_conditions(Some(&[condition]), std::slice::from_ref(&string)).unwrap();
+            assert_eq!(reason.is_none(), expected, "{}", group);
+        }
+    }
 }
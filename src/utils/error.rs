@@ -10,15 +10,46 @@ pub enum UtilsError {
     #[error("File I/O error: {0}")]
     IoError(#[from] io::Error),
 
-    #[error("Command not applicable: {name}")]
-    CommandNotApplicable { name: String },
+    #[error("Command not applicable: {name} ({reason})")]
+    CommandNotApplicable { name: String, reason: String },
 
     #[error("Command {name} failed: {source}")]
     CommandFailed { name: String, source: Error },
 
+    #[error("Aborted because command '{name}' failed")]
+    CommandAborted { name: String },
+
     #[error("Command failed because of parse error: {0}")]
     CommandFailedDueToParseError(#[from] ParserError),
 
+    #[error("Git repository '{url}' could not be fetched: {message}")]
+    GitFailed { url: String, message: String },
+
+    #[error("'{url}' could not be downloaded: {message}")]
+    DownloadFailed { url: String, message: String },
+
+    #[error("Checksum of '{url}' does not match: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        url: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("Archive '{url}' could not be extracted: {message}")]
+    ArchiveExtractionFailed { url: String, message: String },
+
+    #[error("File '{0}' already exists with different contents")]
+    FileConflict(String),
+
+    #[error("Prompt failed: {0}")]
+    PromptFailed(#[from] inquire::InquireError),
+
+    #[error("Invalid configuration location: {0}")]
+    InvalidConfigLocation(String),
+
     #[error("Specified variable '{0}' is not set")]
     VariableNotSet(String),
+
+    #[error("Invalid placeholder '{{{{{0}}}}}', expected '{{{{name}}}}' or '{{{{name|join:separator}}}}'")]
+    InvalidPlaceholder(String),
 }
@@ -1,9 +1,17 @@
-use crate::parser::{TemplateFile, TemplateFileType, Variable};
+use crate::parser::traits::condition::ConditionTrait;
+use crate::parser::{OnConflict, TemplateFile, TemplateFileType, Variable};
+use crate::utils::condition::evaluate_conditions;
 use crate::utils::error::UtilsResult;
-use crate::utils::variable::replace_variables;
-use ignore::WalkBuilder;
+use crate::utils::source::{fetch_source, TemplateSource};
+use crate::utils::variable::{render_contents, replace_variables};
+use crate::utils::UtilsError;
+use ignore::{Walk, WalkBuilder};
+use inquire::Confirm;
+use similar::{ChangeTag, TextDiff};
+use std::fs;
 use std::path::{Path, PathBuf};
-use std::{fs, io};
+
+const BINARY_SNIFF_LENGTH: usize = 8000;
 
 pub fn ensure_project_existence(project_dir: &PathBuf) -> UtilsResult<()> {
     if !project_dir.exists() {
@@ -12,26 +20,44 @@ pub fn ensure_project_existence(project_dir: &PathBuf) -> UtilsResult<()> {
     Ok(())
 }
 
+/// Copies all template files. Existing files with different contents are handled according to the
+/// `on_conflict` policy of the template file, or the given default; an `error` policy (or a failed
+/// prompt) stops processing, and with `no_input` so does `prompt`. Other failures are reported and
+/// processing continues; the number of files that could not be copied is returned.
 pub fn copy_template_files(
     files: &[TemplateFile],
     variables: &[Variable],
     use_filters: &bool,
-) -> UtilsResult<()> {
+    on_conflict: OnConflict,
+    no_input: &bool,
+) -> UtilsResult<usize> {
     println!("Template files: processing...");
 
-    for file in files.iter() {
-        let file_source = PathBuf::from(replace_variables(
-            &file.source.display().to_string(),
-            variables,
-            &false,
-        )?);
-
-        let file_destination = PathBuf::from(replace_variables(
-            &file.destination.display().to_string(),
-            variables,
-            &false,
-        )?);
+    let mut applicable_files = Vec::with_capacity(files.len());
+    for file in files {
+        match skip_reason(file, variables)? {
+            Some(reason) => println!(
+                "Template files: skipped '{}' (false condition: {})",
+                file.label(),
+                reason
+            ),
+            None => applicable_files.push(file),
+        }
+    }
+
+    // fetch all (remote) sources first, so nothing is written when one of them is unavailable
+    let resolved_files = applicable_files
+        .into_iter()
+        .map(|file| {
+            let (source, destination) = resolve_template_paths(file, variables)?;
+            let source = fetch_source(&source, &file.file_type, file.sha256.as_deref())?;
+            Ok((file, source, destination))
+        })
+        .collect::<UtilsResult<Vec<_>>>()?;
 
+    let mut failed = 0;
+    for (file, file_source, file_destination) in resolved_files {
+        let on_conflict = applied_policy(file, on_conflict, no_input);
         match file.file_type {
             TemplateFileType::Folder => {
                 if let Err(e) = fs::create_dir_all(&file_destination) {
@@ -40,20 +66,35 @@ pub fn copy_template_files(
                         &file_destination.to_string_lossy(),
                         e
                     );
+                    failed += 1;
+                    continue;
                 }
 
                 match copy_folder_contents_with_gitignore(
                     &file_source,
                     &file_destination,
                     use_filters,
+                    variables,
+                    &file.render,
+                    on_conflict,
                 ) {
-                    Ok(_) => {
+                    Ok(0) => {
                         println!(
                             "Template files: copied contents of folder '{}' to '{}'",
                             &file_source.to_string_lossy(),
                             &file_destination.to_string_lossy()
                         )
                     }
+                    Ok(folder_failed) => {
+                        eprintln!(
+                            "Template files: {} file(s) of folder '{}' could not be copied to '{}'",
+                            folder_failed,
+                            &file_source.to_string_lossy(),
+                            &file_destination.to_string_lossy()
+                        );
+                        failed += folder_failed;
+                    }
+                    Err(e) if is_fatal(&e) => return Err(e),
                     Err(e) => {
                         eprintln!(
                             "Template files: failed to copy contents of folder '{}' to '{}': {}",
@@ -61,6 +102,7 @@ pub fn copy_template_files(
                             &file_destination.to_string_lossy(),
                             e
                         );
+                        failed += 1;
                     }
                 }
             }
@@ -74,6 +116,8 @@ pub fn copy_template_files(
                         &file_destination.to_string_lossy(),
                         &file.file_type
                     );
+                    failed += 1;
+                    continue;
                 }
                 if let Some(parent) = &file_destination.parent() {
                     if let Err(e) = fs::create_dir_all(parent) {
@@ -82,18 +126,27 @@ pub fn copy_template_files(
                             parent.display(),
                             e
                         );
+                        failed += 1;
                         continue;
                     }
                 }
-                
-                match fs::copy(&file_source, &file_destination) {
-                    Ok(_) => {
+
+                match copy_file(
+                    &file_source,
+                    &file_destination,
+                    variables,
+                    &file.render,
+                    on_conflict,
+                ) {
+                    Ok(false) => {}
+                    Ok(true) => {
                         println!(
                             "Template files: copied file '{}' to '{}'",
                             &file_source.to_string_lossy(),
                             &file_destination.to_string_lossy()
                         )
                     }
+                    Err(e) if is_fatal(&e) => return Err(e),
                     Err(e) => {
                         eprintln!(
                             "Template files: failed to copy file '{}' to '{}': {}",
@@ -101,6 +154,7 @@ pub fn copy_template_files(
                             &file_destination.to_string_lossy(),
                             e
                         );
+                        failed += 1;
                     }
                 };
             }
@@ -108,24 +162,181 @@ pub fn copy_template_files(
     }
     println!("Template files have been processed!");
 
-    Ok(())
+    Ok(failed)
 }
 
-pub fn copy_folder_contents_with_gitignore<P: AsRef<Path>>(
-    source: P,
-    destination: P,
+/// Prints every file `copy_template_files` would create or overwrite, without writing anything.
+/// Existing destinations are listed with the conflict policy that would apply to them.
+pub fn plan_template_files(
+    files: &[TemplateFile],
+    variables: &[Variable],
     use_filters: &bool,
-) -> io::Result<()> {
-    let source_path = source.as_ref();
-    let destination_path = destination.as_ref();
+    on_conflict: OnConflict,
+    no_input: &bool,
+) -> UtilsResult<()> {
+    for file in files.iter() {
+        if let Some(reason) = skip_reason(file, variables)? {
+            println!("  skip      {} (false condition: {})", file.label(), reason);
+            continue;
+        }
 
-    print!(
-        "Use filters ({}) for path: {}\n",
-        *use_filters,
-        &source_path.to_str().unwrap()
+        let on_conflict = applied_policy(file, on_conflict, no_input);
+        let (file_source, file_destination) = resolve_template_paths(file, variables)?;
+        let render_note = match file.render {
+            true => "",
+            false => ", not rendered",
+        };
+        let render_with = file.render.then_some(variables);
+
+        if TemplateSource::parse(&file_source.display().to_string()).is_remote() {
+            println!(
+                "  fetch     {} (into {}{})",
+                file_source.display(),
+                file_destination.display(),
+                render_note
+            );
+            continue;
+        }
+
+        match file.file_type {
+            TemplateFileType::Folder => {
+                for entry in template_walker(&file_source, use_filters) {
+                    let entry = match entry {
+                        Ok(entry) => entry,
+                        Err(e) => {
+                            println!("  error {}", e);
+                            continue;
+                        }
+                    };
+                    if entry.path().is_dir() {
+                        continue;
+                    }
+                    if let Ok(relative_path) = entry.path().strip_prefix(&file_source) {
+                        print_planned_file(
+                            entry.path(),
+                            &file_destination.join(relative_path),
+                            render_note,
+                            on_conflict,
+                            render_with,
+                        );
+                    }
+                }
+            }
+            TemplateFileType::File => print_planned_file(
+                &file_source,
+                &file_destination,
+                render_note,
+                on_conflict,
+                render_with,
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// The conflict policy of a template file, or the given default.
+fn applied_policy(file: &TemplateFile, on_conflict: OnConflict, no_input: &bool) -> OnConflict {
+    let on_conflict = file.on_conflict.unwrap_or(on_conflict);
+    match *no_input {
+        true => on_conflict.without_input(),
+        false => on_conflict,
+    }
+}
+
+/// Evaluates the conditions of a template file; returns why it is skipped when they do not hold.
+fn skip_reason(file: &TemplateFile, variables: &[Variable]) -> UtilsResult<Option<String>> {
+    match file.get_conditions() {
+        Some(conditions) => Ok(evaluate_conditions(Some(conditions), variables)?),
+        None => Ok(None),
+    }
+}
+
+/// Prints what happens to a single file. The file is read (and rendered with `render_with`), so
+/// files that would fail to copy are listed as errors.
+fn print_planned_file(
+    source: &Path,
+    destination: &Path,
+    note: &str,
+    on_conflict: OnConflict,
+    render_with: Option<&[Variable]>,
+) {
+    if let Err(e) = check_contents(source, render_with) {
+        println!(
+            "  {:<9} {} (from {}: {})",
+            "error",
+            destination.display(),
+            source.display(),
+            e
+        );
+        return;
+    }
+
+    let action = match (destination.exists(), on_conflict) {
+        (false, _) => "create".to_string(),
+        (true, OnConflict::Error) => "conflict".to_string(),
+        (true, on_conflict) => on_conflict.to_string(),
+    };
+    println!(
+        "  {:<9} {} (from {}{})",
+        action,
+        destination.display(),
+        source.display(),
+        note
     );
+}
+
+fn check_contents(source: &Path, render_with: Option<&[Variable]>) -> UtilsResult<()> {
+    let contents = fs::read(source)?;
+    if let (Some(variables), Some(text)) = (render_with, text_contents(&contents)) {
+        render_contents(text, variables)?;
+    }
+    Ok(())
+}
+
+/// Finds the YAML, JSON and TOML files below `dir` (at most three levels deep) that may be
+/// configurations, sorted by path.
+pub fn find_configurations(dir: &Path) -> Vec<PathBuf> {
+    let mut configurations: Vec<PathBuf> = WalkBuilder::new(dir)
+        .max_depth(Some(3))
+        .build()
+        .filter_map(Result::ok)
+        .map(|entry| entry.into_path())
+        .filter(|path| {
+            path.is_file()
+                && matches!(
+                    path.extension().and_then(|e| e.to_str()),
+                    Some("yaml" | "yml" | "json" | "toml")
+                )
+        })
+        .collect();
+    configurations.sort();
+    configurations
+}
+
+/// Replaces variables in the source and destination of a template file.
+fn resolve_template_paths(
+    file: &TemplateFile,
+    variables: &[Variable],
+) -> UtilsResult<(PathBuf, PathBuf)> {
+    let file_source = PathBuf::from(replace_variables(
+        &file.source.display().to_string(),
+        variables,
+        &false,
+    )?);
+
+    let file_destination = PathBuf::from(replace_variables(
+        &file.destination.display().to_string(),
+        variables,
+        &false,
+    )?);
 
-    let walker = WalkBuilder::new(source_path)
+    Ok((file_source, file_destination))
+}
+
+/// Walks a template folder. `.git` is always skipped: checkouts of git sources are worktrees whose
+/// `.git` file points into the cache.
+fn template_walker(source: &Path, use_filters: &bool) -> Walk {
+    WalkBuilder::new(source)
         .follow_links(true)
         .hidden(false)
         .parents(true)
@@ -134,7 +345,30 @@ pub fn copy_folder_contents_with_gitignore<P: AsRef<Path>>(
         .git_global(*use_filters)
         .git_exclude(*use_filters)
         .add_custom_ignore_filename(".dignore")
-        .build();
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build()
+}
+
+/// Copies the contents of a folder, returning the number of files that could not be copied.
+pub fn copy_folder_contents_with_gitignore<P: AsRef<Path>>(
+    source: P,
+    destination: P,
+    use_filters: &bool,
+    variables: &[Variable],
+    render: &bool,
+    on_conflict: OnConflict,
+) -> UtilsResult<usize> {
+    let source_path = source.as_ref();
+    let destination_path = destination.as_ref();
+
+    println!(
+        "Use filters ({}) for path: {}",
+        *use_filters,
+        &source_path.to_str().unwrap()
+    );
+
+    let walker = template_walker(source_path, use_filters);
+    let mut failed = 0;
 
     for entry in walker {
         match entry {
@@ -152,6 +386,7 @@ pub fn copy_folder_contents_with_gitignore<P: AsRef<Path>>(
                                     dest_path.display(),
                                     e
                                 );
+                                failed += 1;
                                 continue;
                             }
                         } else {
@@ -162,16 +397,23 @@ pub fn copy_folder_contents_with_gitignore<P: AsRef<Path>>(
                                         parent.display(),
                                         e
                                     );
+                                    failed += 1;
                                     continue;
                                 }
                             }
-                            if let Err(e) = fs::copy(&entry_path, &dest_path) {
-                                eprintln!(
-                                    "Failed to copy file '{}' to '{}': {}",
-                                    entry_path.display(),
-                                    dest_path.display(),
-                                    e
-                                );
+                            match copy_file(entry_path, &dest_path, variables, render, on_conflict)
+                            {
+                                Ok(_) => {}
+                                Err(e) if is_fatal(&e) => return Err(e),
+                                Err(e) => {
+                                    eprintln!(
+                                        "Failed to copy file '{}' to '{}': {}",
+                                        entry_path.display(),
+                                        dest_path.display(),
+                                        e
+                                    );
+                                    failed += 1;
+                                }
                             }
                         }
                     }
@@ -181,11 +423,147 @@ pub fn copy_folder_contents_with_gitignore<P: AsRef<Path>>(
                             entry_path.display(),
                             e
                         );
+                        failed += 1;
                     }
                 };
             }
-            Err(e) => eprintln!("Error encountered during directory traversal: {}", e),
+            Err(e) => {
+                eprintln!("Error encountered during directory traversal: {}", e);
+                failed += 1;
+            }
         }
     }
-    Ok(())
+    Ok(failed)
+}
+
+/// Copies a single file, rendering `{{variable}}` placeholders in its contents when `render` is
+/// set. Binary files (containing NUL bytes or invalid UTF-8) are always copied as-is. Returns
+/// `false` when an existing file was kept because of the conflict policy.
+pub fn copy_file(
+    source: &Path,
+    destination: &Path,
+    variables: &[Variable],
+    render: &bool,
+    on_conflict: OnConflict,
+) -> UtilsResult<bool> {
+    let contents = fs::read(source)?;
+    let contents = match (*render, text_contents(&contents)) {
+        (true, Some(text)) => render_contents(text, variables)?.into_bytes(),
+        _ => contents,
+    };
+
+    if !resolve_conflict(destination, &contents, on_conflict)? {
+        return Ok(false);
+    }
+
+    fs::write(destination, &contents)?;
+    fs::set_permissions(destination, fs::metadata(source)?.permissions())?;
+    Ok(true)
+}
+
+/// Decides whether `contents` may be written to `destination`. Existing files with the same
+/// contents are never a conflict.
+fn resolve_conflict(
+    destination: &Path,
+    contents: &[u8],
+    on_conflict: OnConflict,
+) -> UtilsResult<bool> {
+    if !destination.is_file() {
+        return Ok(true);
+    }
+    let existing = fs::read(destination)?;
+    if existing == contents {
+        return Ok(true);
+    }
+
+    match on_conflict {
+        OnConflict::Overwrite => Ok(true),
+        OnConflict::Skip => {
+            println!(
+                "Template files: kept existing file '{}'",
+                destination.display()
+            );
+            Ok(false)
+        }
+        OnConflict::Error => Err(UtilsError::FileConflict(destination.display().to_string())),
+        OnConflict::Backup => {
+            let backup = backup_path(destination);
+            fs::rename(destination, &backup)?;
+            println!(
+                "Template files: moved existing file '{}' to '{}'",
+                destination.display(),
+                backup.display()
+            );
+            Ok(true)
+        }
+        OnConflict::Prompt => {
+            print_diff(destination, &existing, contents);
+            let overwrite = Confirm::new(&format!("Overwrite '{}'?", destination.display()))
+                .with_default(false)
+                .prompt()?;
+            if !overwrite {
+                println!(
+                    "Template files: kept existing file '{}'",
+                    destination.display()
+                );
+            }
+            Ok(overwrite)
+        }
+    }
+}
+
+/// Returns `<file>.bak`, or `<file>.bak.<n>` when earlier backups exist.
+fn backup_path(path: &Path) -> PathBuf {
+    let backup = PathBuf::from(format!("{}.bak", path.display()));
+    let mut candidate = backup.clone();
+    let mut n = 1;
+    while candidate.exists() {
+        candidate = PathBuf::from(format!("{}.{}", backup.display(), n));
+        n += 1;
+    }
+    candidate
+}
+
+fn print_diff(destination: &Path, existing: &[u8], contents: &[u8]) {
+    let (Some(existing), Some(contents)) = (text_contents(existing), text_contents(contents))
+    else {
+        println!("Binary file '{}' differs", destination.display());
+        return;
+    };
+
+    println!("\x1b[1m--- {} (existing)\x1b[0m", destination.display());
+    println!("\x1b[1m+++ {} (template)\x1b[0m", destination.display());
+    let diff = TextDiff::from_lines(existing, contents);
+    for hunk in diff.unified_diff().context_radius(3).iter_hunks() {
+        println!("{}", hunk.header());
+        for change in hunk.iter_changes() {
+            let (sign, color) = match change.tag() {
+                ChangeTag::Delete => ("-", "\x1b[31m"),
+                ChangeTag::Insert => ("+", "\x1b[32m"),
+                ChangeTag::Equal => (" ", ""),
+            };
+            println!(
+                "{}{}{}\x1b[0m",
+                color,
+                sign,
+                change.value().trim_end_matches(['\r', '\n'])
+            );
+        }
+    }
+}
+
+/// Conflicts and failed prompts stop processing, other errors only skip the file.
+fn is_fatal(error: &UtilsError) -> bool {
+    matches!(
+        error,
+        UtilsError::FileConflict(_) | UtilsError::PromptFailed(_)
+    )
+}
+
+pub fn text_contents(contents: &[u8]) -> Option<&str> {
+    let sample = &contents[..contents.len().min(BINARY_SNIFF_LENGTH)];
+    if sample.contains(&0) {
+        return None;
+    }
+    std::str::from_utf8(contents).ok()
 }
@@ -2,7 +2,11 @@ pub mod command;
 pub mod condition;
 mod error;
 pub mod file;
+pub mod source;
+pub mod update;
 mod variable;
 
+pub use command::CommandFailure;
 pub use command::Project;
 pub use error::UtilsError;
+pub use variable::replace_environment_variables;
@@ -0,0 +1,705 @@
+use crate::parser::{TemplateFile, TemplateFileType};
+use crate::utils::error::UtilsResult;
+use crate::utils::UtilsError;
+use flate2::read::GzDecoder;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{env, fs};
+use zip::ZipArchive;
+
+const GIT_PREFIX: &str = "git+";
+const HTTP_PREFIXES: [&str; 2] = ["http://", "https://"];
+
+/// Where a template file or folder comes from.
+#[derive(Debug, PartialEq)]
+pub enum TemplateSource {
+    Local(PathBuf),
+    /// `git+<url>[#<ref>[:<subdirectory>]]`, e.g. `git+file:///srv/templates.git#v2:node/`
+    Git {
+        url: String,
+        reference: Option<String>,
+        subdirectory: Option<PathBuf>,
+    },
+    /// `http(s)://<url>[#<subdirectory>]`, a single file or a `.tar.gz`/`.zip` archive
+    Http {
+        url: String,
+        subdirectory: Option<PathBuf>,
+    },
+}
+
+impl TemplateSource {
+    pub fn parse(source: &str) -> Self {
+        if HTTP_PREFIXES
+            .iter()
+            .any(|prefix| source.starts_with(prefix))
+        {
+            let (url, subdirectory) = match source.split_once('#') {
+                Some((url, subdirectory)) => (url, Some(subdirectory)),
+                None => (source, None),
+            };
+            return TemplateSource::Http {
+                url: url.to_string(),
+                subdirectory: subdirectory.filter(|s| !s.is_empty()).map(PathBuf::from),
+            };
+        }
+
+        let Some(git_source) = source.strip_prefix(GIT_PREFIX) else {
+            return TemplateSource::Local(PathBuf::from(source));
+        };
+
+        let (url, reference, subdirectory) = match git_source.split_once('#') {
+            None => (git_source, None, None),
+            Some((url, fragment)) => match fragment.split_once(':') {
+                Some((reference, subdirectory)) => (url, Some(reference), Some(subdirectory)),
+                None => (url, Some(fragment), None),
+            },
+        };
+
+        TemplateSource::Git {
+            url: url.to_string(),
+            reference: reference.filter(|r| !r.is_empty()).map(String::from),
+            subdirectory: subdirectory.filter(|s| !s.is_empty()).map(PathBuf::from),
+        }
+    }
+
+    pub fn is_remote(&self) -> bool {
+        !matches!(self, TemplateSource::Local(_))
+    }
+}
+
+/// Makes the source available on the local filesystem and returns its path. Remote sources are
+/// fetched into the cache; when fetching fails, a previously cached copy is used. Archives
+/// downloaded for a folder are extracted, and verified against `sha256` when given.
+pub fn fetch_source(
+    source: &Path,
+    file_type: &TemplateFileType,
+    sha256: Option<&str>,
+) -> UtilsResult<PathBuf> {
+    match TemplateSource::parse(&source.display().to_string()) {
+        TemplateSource::Local(path) => Ok(path),
+        TemplateSource::Git {
+            url,
+            reference,
+            subdirectory,
+        } => {
+            let checkout = fetch_git_repository(&url, reference.as_deref())?;
+            Ok(match subdirectory {
+                Some(subdirectory) => checkout.join(subdirectory),
+                None => checkout,
+            })
+        }
+        TemplateSource::Http { url, subdirectory } => {
+            let download = download_file(&url, sha256)?;
+            match file_type {
+                TemplateFileType::File => Ok(download),
+                TemplateFileType::Folder => {
+                    let extracted = extract_archive(&url, &download)?;
+                    Ok(match subdirectory {
+                        Some(subdirectory) => extracted.join(subdirectory),
+                        None => extracted,
+                    })
+                }
+            }
+        }
+    }
+}
+
+/// A configuration made available on the local filesystem.
+pub struct FetchedConfig {
+    pub path: PathBuf,
+    /// The location relative template sources are resolved against: the directory containing a
+    /// local configuration, or the location a remote configuration was fetched from.
+    pub base: String,
+    /// The commit a configuration from a Git repository was read at.
+    pub revision: Option<String>,
+}
+
+/// Makes a configuration file available on the local filesystem, fetching remote configurations
+/// like template sources.
+pub fn fetch_config(config: &Path) -> UtilsResult<FetchedConfig> {
+    match TemplateSource::parse(&config.display().to_string()) {
+        TemplateSource::Local(path) => {
+            let parent = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            let base = fs::canonicalize(parent).unwrap_or(parent.to_path_buf());
+            Ok(FetchedConfig {
+                base: base.display().to_string(),
+                path,
+                revision: None,
+            })
+        }
+        TemplateSource::Git {
+            url,
+            reference,
+            subdirectory,
+        } => {
+            let Some(config_file) = subdirectory else {
+                return Err(UtilsError::InvalidConfigLocation(format!(
+                    "'{}' does not name a file, expected git+<url>#<ref>:<path to config>",
+                    config.display()
+                )));
+            };
+            let checkout = fetch_git_repository(&url, reference.as_deref())?;
+            let revision = run_git(&url, &checkout, &["rev-parse", "HEAD"])?;
+            let path = checkout.join(config_file);
+            Ok(FetchedConfig {
+                base: path.parent().unwrap_or(&path).display().to_string(),
+                path,
+                revision: Some(revision),
+            })
+        }
+        TemplateSource::Http { url, .. } => {
+            let path = download_file(&url, None)?;
+            let url_path = url.split(['?', '#']).next().unwrap_or(&url);
+            let base = url_path
+                .rsplit_once('/')
+                .map_or(url_path, |(base, _)| base)
+                .to_string();
+            Ok(FetchedConfig {
+                path,
+                base,
+                revision: None,
+            })
+        }
+    }
+}
+
+/// Returns the Git location of a configuration with its reference replaced by `revision`, so the
+/// exact version of a template can be fetched again. Other locations can't be pinned.
+pub fn pin_config_location(config: &str, revision: &str) -> Option<String> {
+    match TemplateSource::parse(config) {
+        TemplateSource::Git {
+            url,
+            subdirectory: Some(config_file),
+            ..
+        } => Some(format!(
+            "{}{}#{}:{}",
+            GIT_PREFIX,
+            url,
+            revision,
+            config_file.display()
+        )),
+        _ => None,
+    }
+}
+
+/// Resolves relative template sources against `base`, the location of the configuration, so a
+/// template works regardless of the directory dynaplate is run from.
+pub fn resolve_relative_sources(files: &mut [TemplateFile], base: &str) {
+    for file in files.iter_mut() {
+        file.source = PathBuf::from(resolve_relative_location(
+            &file.source.display().to_string(),
+            base,
+        ));
+    }
+}
+
+/// Resolves a location relative to `base`, a local directory or URL. Remote, absolute and
+/// placeholder locations (e.g. `{{working_dir}}/...`) are returned as they are.
+pub fn resolve_relative_location(location: &str, base: &str) -> String {
+    if TemplateSource::parse(location).is_remote()
+        || Path::new(location).is_absolute()
+        || location.starts_with("{{")
+    {
+        return location.to_string();
+    }
+
+    match TemplateSource::parse(base) {
+        TemplateSource::Http { .. } => format!("{}/{}", base.trim_end_matches('/'), location),
+        _ => Path::new(base).join(location).display().to_string(),
+    }
+}
+
+/// Directory in which remote templates are cached: `$DYNAPLATE_CACHE_DIR`, or `dynaplate` inside
+/// the user's cache directory.
+pub fn cache_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("DYNAPLATE_CACHE_DIR") {
+        return PathBuf::from(dir);
+    }
+    match (env::var_os("XDG_CACHE_HOME"), env::var_os("HOME")) {
+        (Some(cache), _) => PathBuf::from(cache).join("dynaplate"),
+        (None, Some(home)) => PathBuf::from(home).join(".cache").join("dynaplate"),
+        (None, None) => env::temp_dir().join("dynaplate"),
+    }
+}
+
+/// Directory searched by `dynaplate list`: `$DYNAPLATE_TEMPLATE_DIR`, or `dynaplate/templates`
+/// inside the user's config directory.
+pub fn template_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("DYNAPLATE_TEMPLATE_DIR") {
+        return PathBuf::from(dir);
+    }
+    match (env::var_os("XDG_CONFIG_HOME"), env::var_os("HOME")) {
+        (Some(config), _) => PathBuf::from(config).join("dynaplate").join("templates"),
+        (None, Some(home)) => PathBuf::from(home)
+            .join(".config")
+            .join("dynaplate")
+            .join("templates"),
+        (None, None) => PathBuf::from("templates"),
+    }
+}
+
+/// Turns a URL into a name that can be used as a directory in the cache.
+pub fn cache_key(url: &str) -> String {
+    url.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Keeps a mirror of the repository in the cache, with a separate worktree per reference so
+/// different references of the same repository can be used side by side.
+fn fetch_git_repository(url: &str, reference: Option<&str>) -> UtilsResult<PathBuf> {
+    let repository_dir = cache_dir().join("git").join(cache_key(url));
+    let mirror = repository_dir.join("repository.git");
+
+    if mirror.exists() {
+        if let Err(e) = run_git(url, &mirror, &["fetch", "--quiet", "--prune", "origin"]) {
+            eprintln!("Warning: using cached copy of '{}': {}", url, e);
+        }
+    } else {
+        fs::create_dir_all(&repository_dir)?;
+        run_git(
+            url,
+            &repository_dir,
+            &["clone", "--quiet", "--mirror", url, "repository.git"],
+        )?;
+    }
+
+    let target = reference.unwrap_or("HEAD");
+    let checkout = repository_dir.join("checkouts").join(cache_key(target));
+    // resolved in the mirror: inside a worktree, `HEAD` would refer to the worktree itself
+    let commit = run_git(
+        url,
+        &mirror,
+        &["rev-parse", "--verify", &format!("{}^{{commit}}", target)],
+    )?;
+    if checkout.exists() {
+        run_git(
+            url,
+            &checkout,
+            &["checkout", "--quiet", "--force", "--detach", &commit],
+        )?;
+    } else {
+        run_git(
+            url,
+            &mirror,
+            &[
+                "worktree",
+                "add",
+                "--quiet",
+                "--force",
+                "--detach",
+                &checkout.display().to_string(),
+                &commit,
+            ],
+        )?;
+    }
+
+    Ok(checkout)
+}
+
+fn download_file(url: &str, sha256: Option<&str>) -> UtilsResult<PathBuf> {
+    let download = cache_dir().join("http").join(cache_key(url));
+
+    // only network problems fall back to the cached copy, an error status of the server does not
+    let response = ureq::get(url)
+        .call()
+        .map_err(|e| (matches!(e, ureq::Error::Transport(_)), e.to_string()));
+    let contents = response.and_then(|response| {
+        let mut contents = Vec::new();
+        response
+            .into_reader()
+            .read_to_end(&mut contents)
+            .map(|_| contents)
+            .map_err(|e| (true, e.to_string()))
+    });
+
+    match contents {
+        Ok(contents) => {
+            verify_checksum(url, &contents, sha256)?;
+            fs::create_dir_all(cache_dir().join("http"))?;
+            fs::write(&download, contents)?;
+        }
+        Err((true, message)) if download.exists() => {
+            eprintln!("Warning: using cached copy of '{}': {}", url, message);
+            verify_checksum(url, &fs::read(&download)?, sha256)?;
+        }
+        Err((_, message)) => {
+            return Err(UtilsError::DownloadFailed {
+                url: url.to_string(),
+                message,
+            })
+        }
+    }
+
+    Ok(download)
+}
+
+fn verify_checksum(url: &str, contents: &[u8], sha256: Option<&str>) -> UtilsResult<()> {
+    let Some(expected) = sha256 else {
+        return Ok(());
+    };
+
+    let actual: String = Sha256::digest(contents)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    match actual.eq_ignore_ascii_case(expected.trim()) {
+        true => Ok(()),
+        false => Err(UtilsError::ChecksumMismatch {
+            url: url.to_string(),
+            expected: expected.to_string(),
+            actual,
+        }),
+    }
+}
+
+/// Extracts a `.tar.gz`/`.tgz` or `.zip` archive next to the downloaded file.
+fn extract_archive(url: &str, archive: &Path) -> UtilsResult<PathBuf> {
+    let extracted = archive.with_extension("extracted");
+    if extracted.exists() {
+        fs::remove_dir_all(&extracted)?;
+    }
+    fs::create_dir_all(&extracted)?;
+
+    let extraction_failed = |message: String| UtilsError::ArchiveExtractionFailed {
+        url: url.to_string(),
+        message,
+    };
+
+    // the archive type is determined by the path of the URL, without query or fragment
+    let url_path = url.split(['?', '#']).next().unwrap_or(url);
+    if url_path.ends_with(".tar.gz") || url_path.ends_with(".tgz") {
+        tar::Archive::new(GzDecoder::new(File::open(archive)?))
+            .unpack(&extracted)
+            .map_err(|e| extraction_failed(e.to_string()))?;
+    } else if url_path.ends_with(".zip") {
+        ZipArchive::new(File::open(archive)?)
+            .and_then(|mut zip| zip.extract(&extracted))
+            .map_err(|e| extraction_failed(e.to_string()))?;
+    } else {
+        return Err(extraction_failed(
+            "only .tar.gz, .tgz and .zip archives can be used as a folder".to_string(),
+        ));
+    }
+
+    Ok(extracted)
+}
+
+fn run_git(url: &str, dir: &Path, args: &[&str]) -> UtilsResult<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| UtilsError::GitFailed {
+            url: url.to_string(),
+            message: e.to_string(),
+        })?;
+
+    match output.status.success() {
+        true => Ok(String::from_utf8_lossy(&output.stdout).trim().to_string()),
+        false => Err(UtilsError::GitFailed {
+            url: url.to_string(),
+            message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::OnConflict;
+    use crate::utils::file::copy_folder_contents_with_gitignore;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::OnceLock;
+    use std::thread;
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    /// All tests share one cache directory; every test uses its own sources, so their cache
+    /// entries never collide.
+    fn test_dir(name: &str) -> PathBuf {
+        static CACHE: OnceLock<PathBuf> = OnceLock::new();
+        let root = CACHE.get_or_init(|| {
+            let root = env::temp_dir().join(format!("dynaplate-tests-{}", std::process::id()));
+            env::set_var("DYNAPLATE_CACHE_DIR", root.join("cache"));
+            root
+        });
+        let dir = root.join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    fn commit_version(work: &Path, version: &str) {
+        fs::write(work.join("version.txt"), version).unwrap();
+        fs::write(work.join("node").join("package.json"), version).unwrap();
+        git(work, &["add", "."]);
+        git(work, &["commit", "--quiet", "-m", version]);
+        git(work, &["tag", version]);
+    }
+
+    fn fetch_folder(source: &str) -> PathBuf {
+        fetch_source(Path::new(source), &TemplateFileType::Folder, None).unwrap()
+    }
+
+    #[test]
+    fn fetches_git_sources_from_a_bare_repository() {
+        let dir = test_dir("git");
+        let work = dir.join("work");
+        fs::create_dir_all(work.join("node")).unwrap();
+        git(&work, &["init", "--quiet", "--initial-branch", "main"]);
+        commit_version(&work, "v1");
+        git(
+            &dir,
+            &["clone", "--quiet", "--bare", "work", "templates.git"],
+        );
+        let url = format!("git+file://{}", dir.join("templates.git").display());
+
+        let checkout = fetch_folder(&url);
+        assert_eq!(
+            fs::read_to_string(checkout.join("version.txt")).unwrap(),
+            "v1"
+        );
+
+        // the checkout is a worktree, whose `.git` file must not reach the project
+        let project = dir.join("project");
+        let failed = copy_folder_contents_with_gitignore(
+            checkout.as_path(),
+            project.as_path(),
+            &true,
+            &[],
+            &false,
+            OnConflict::Overwrite,
+        )
+        .unwrap();
+        assert_eq!(failed, 0);
+        assert!(project.join("version.txt").is_file());
+        assert!(!project.join(".git").exists());
+
+        // the cached mirror and checkout are updated, also when no reference is given
+        commit_version(&work, "v2");
+        git(
+            &work,
+            &["push", "--quiet", "--tags", "../templates.git", "main"],
+        );
+
+        let checkout = fetch_folder(&url);
+        assert_eq!(
+            fs::read_to_string(checkout.join("version.txt")).unwrap(),
+            "v2"
+        );
+
+        let subdirectory = fetch_folder(&format!("{}#v1:node", url));
+        assert_eq!(
+            fs::read_to_string(subdirectory.join("package.json")).unwrap(),
+            "v1"
+        );
+
+        let branch = fetch_folder(&format!("{}#main", url));
+        assert_eq!(
+            fs::read_to_string(branch.join("version.txt")).unwrap(),
+            "v2"
+        );
+    }
+
+    /// Serves each of the `routes` (path and body) once over HTTP, answering other requests with
+    /// 404. After the given number of requests the server stops and connections are refused.
+    /// Returns the base URL.
+    fn serve(routes: Vec<(&'static str, Vec<u8>)>, requests: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            let mut routes = routes;
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut request_line = String::new();
+                let mut reader = BufReader::new(&stream);
+                reader.read_line(&mut request_line).unwrap();
+                let mut header = String::new();
+                while reader.read_line(&mut header).unwrap() > 2 {
+                    header.clear();
+                }
+
+                let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+                let (status, body) = match routes.iter().position(|(route, _)| *route == path) {
+                    Some(idx) => ("200 OK", routes.remove(idx).1),
+                    None => ("404 Not Found", Vec::new()),
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                )
+                .unwrap();
+                stream.write_all(&body).unwrap();
+            }
+        });
+        base
+    }
+
+    const TEMPLATE: [(&str, &str); 2] = [
+        ("template/README.md", "# {{name}}"),
+        ("template/node/package.json", "{}"),
+    ];
+
+    fn tar_gz() -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (path, contents) in TEMPLATE {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, contents.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn zip() -> Vec<u8> {
+        let mut writer = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (path, contents) in TEMPLATE {
+            writer
+                .start_file(path, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn sha256_hex(contents: &[u8]) -> String {
+        Sha256::digest(contents)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    #[test]
+    fn downloads_a_single_file() {
+        test_dir("http");
+        let base = serve(vec![("/LICENSE", b"MIT".to_vec())], 1);
+
+        let file = fetch_source(
+            Path::new(&format!("{}/LICENSE", base)),
+            &TemplateFileType::File,
+            None,
+        )
+        .unwrap();
+        assert_eq!(fs::read_to_string(file).unwrap(), "MIT");
+    }
+
+    #[test]
+    fn extracts_archives_and_selects_a_subdirectory() {
+        test_dir("http");
+        let base = serve(
+            vec![
+                ("/template.tar.gz", tar_gz()),
+                ("/template.zip", zip()),
+                ("/template.tar.gz", tar_gz()),
+            ],
+            3,
+        );
+
+        for archive in ["template.tar.gz", "template.zip"] {
+            let folder = fetch_folder(&format!("{}/{}", base, archive));
+            assert_eq!(
+                fs::read_to_string(folder.join("template").join("README.md")).unwrap(),
+                "# {{name}}"
+            );
+        }
+
+        let subdirectory = fetch_folder(&format!("{}/template.tar.gz#template/node", base));
+        assert_eq!(
+            fs::read_to_string(subdirectory.join("package.json")).unwrap(),
+            "{}"
+        );
+    }
+
+    #[test]
+    fn verifies_the_checksum_of_downloads() {
+        test_dir("http");
+        let base = serve(
+            vec![("/LICENSE", b"MIT".to_vec()), ("/LICENSE", b"MIT".to_vec())],
+            2,
+        );
+        let source = format!("{}/LICENSE", base);
+
+        let file = fetch_source(
+            Path::new(&source),
+            &TemplateFileType::File,
+            Some(&sha256_hex(b"MIT")),
+        );
+        assert!(file.is_ok());
+
+        let mismatch = fetch_source(
+            Path::new(&source),
+            &TemplateFileType::File,
+            Some(&sha256_hex(b"Apache-2.0")),
+        );
+        assert!(matches!(mismatch, Err(UtilsError::ChecksumMismatch { .. })));
+    }
+
+    #[test]
+    fn falls_back_to_the_cached_copy() {
+        test_dir("http");
+        let base = serve(vec![("/LICENSE", b"MIT".to_vec())], 1);
+        let source = format!("{}/LICENSE", base);
+        fetch_source(Path::new(&source), &TemplateFileType::File, None).unwrap();
+
+        // the server has stopped, so the second download fails
+        let cached = fetch_source(
+            Path::new(&source),
+            &TemplateFileType::File,
+            Some(&sha256_hex(b"MIT")),
+        )
+        .unwrap();
+        assert_eq!(fs::read_to_string(cached).unwrap(), "MIT");
+
+        let missing = fetch_source(
+            Path::new(&format!("{}/missing", base)),
+            &TemplateFileType::File,
+            None,
+        );
+        assert!(matches!(missing, Err(UtilsError::DownloadFailed { .. })));
+    }
+
+    #[test]
+    fn reports_error_statuses_instead_of_using_the_cached_copy() {
+        test_dir("http");
+        let base = serve(vec![("/LICENSE", b"MIT".to_vec())], 2);
+        let source = format!("{}/LICENSE", base);
+        fetch_source(Path::new(&source), &TemplateFileType::File, None).unwrap();
+
+        // the file is gone from the server, which answers 404
+        let gone = fetch_source(Path::new(&source), &TemplateFileType::File, None);
+        assert!(matches!(gone, Err(UtilsError::DownloadFailed { .. })));
+    }
+}
@@ -0,0 +1,341 @@
+use crate::utils::error::UtilsResult;
+use crate::utils::file::text_contents;
+use diffy::{ConflictStyle, MergeOptions};
+use ignore::WalkBuilder;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Applies the changes between two renderings of a template, `old` and `new`, to `project`.
+/// Files the project didn't change are replaced and changed files are merged, leaving conflict
+/// markers where both the project and the template changed the same lines. Returns the files
+/// that have conflicts.
+pub fn apply_template_update(
+    old: &Path,
+    new: &Path,
+    project: &Path,
+    dry_run: bool,
+) -> UtilsResult<Vec<PathBuf>> {
+    let old_files = list_files(old)?;
+    let new_files = list_files(new)?;
+    let mut conflicts = Vec::new();
+
+    for relative in old_files.union(&new_files) {
+        let old_contents = read_if_exists(&old.join(relative))?;
+        let new_contents = read_if_exists(&new.join(relative))?;
+        let target = project.join(relative);
+        let project_contents = read_if_exists(&target)?;
+
+        // nothing to do when the template didn't change, or the project already has its changes
+        if old_contents == new_contents || new_contents == project_contents {
+            continue;
+        }
+
+        let Some(new_contents) = new_contents else {
+            match old_contents == project_contents {
+                true => {
+                    print_update_action("remove", &target);
+                    if !dry_run {
+                        fs::remove_file(&target)?;
+                    }
+                }
+                false => print_update_action("keep", &target),
+            }
+            continue;
+        };
+
+        let Some(project_contents) = project_contents else {
+            match old_contents {
+                // the file was deleted from the project, so it is not restored
+                Some(_) => print_update_action("skip", &target),
+                None => {
+                    print_update_action("add", &target);
+                    if !dry_run {
+                        write_file(&new.join(relative), &target, &new_contents)?;
+                    }
+                }
+            }
+            continue;
+        };
+
+        if old_contents.as_ref() == Some(&project_contents) {
+            print_update_action("update", &target);
+            if !dry_run {
+                write_file(&new.join(relative), &target, &new_contents)?;
+            }
+            continue;
+        }
+
+        let old_contents = old_contents.unwrap_or_default();
+        let texts = (
+            text_contents(&old_contents),
+            text_contents(&project_contents),
+            text_contents(&new_contents),
+        );
+        let (Some(old_text), Some(project_text), Some(new_text)) = texts else {
+            print_update_action("conflict", &target);
+            eprintln!(
+                "Binary file '{}' was changed in both the project and the template, kept the project version",
+                target.display()
+            );
+            conflicts.push(target);
+            continue;
+        };
+
+        let merged = MergeOptions::new()
+            .set_conflict_style(ConflictStyle::Merge)
+            .merge(old_text, project_text, new_text);
+        let merged = match merged {
+            Ok(merged) => {
+                print_update_action("merge", &target);
+                merged
+            }
+            Err(merged) => {
+                print_update_action("conflict", &target);
+                conflicts.push(target.clone());
+                merged
+            }
+        };
+        if !dry_run {
+            fs::write(&target, merged)?;
+        }
+    }
+
+    Ok(conflicts)
+}
+
+fn print_update_action(action: &str, path: &Path) {
+    println!("  {:<9} {}", action, path.display());
+}
+
+/// Lists all files below `dir`, relative to it.
+fn list_files(dir: &Path) -> UtilsResult<BTreeSet<PathBuf>> {
+    let mut files = BTreeSet::new();
+    if !dir.exists() {
+        return Ok(files);
+    }
+
+    for entry in WalkBuilder::new(dir).standard_filters(false).build() {
+        let entry = entry.map_err(std::io::Error::other)?;
+        if !entry.path().is_file() {
+            continue;
+        }
+        if let Ok(relative) = entry.path().strip_prefix(dir) {
+            files.insert(relative.to_path_buf());
+        }
+    }
+    Ok(files)
+}
+
+fn read_if_exists(path: &Path) -> UtilsResult<Option<Vec<u8>>> {
+    match path.is_file() {
+        true => Ok(Some(fs::read(path)?)),
+        false => Ok(None),
+    }
+}
+
+fn write_file(rendered: &Path, target: &Path, contents: &[u8]) -> UtilsResult<()> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(target, contents)?;
+    fs::set_permissions(target, fs::metadata(rendered)?.permissions())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    /// A file in the old and new rendering of the template and in the project, `None` where it
+    /// does not exist.
+    type Versions<'a> = (
+        &'a str,
+        Option<&'a [u8]>,
+        Option<&'a [u8]>,
+        Option<&'a [u8]>,
+    );
+
+    /// Writes the versions of the files to `old`, `new` and `project` below a fresh directory and
+    /// applies the update, returning the project directory and the conflicts.
+    fn update(name: &str, files: &[Versions], dry_run: bool) -> (PathBuf, Vec<PathBuf>) {
+        let dir = env::temp_dir()
+            .join(format!("dynaplate-update-tests-{}", std::process::id()))
+            .join(name);
+        let _ = fs::remove_dir_all(&dir);
+
+        for (path, old, new, project) in files {
+            for (version, contents) in [("old", old), ("new", new), ("project", project)] {
+                if let Some(contents) = contents {
+                    let file = dir.join(version).join(path);
+                    fs::create_dir_all(file.parent().unwrap()).unwrap();
+                    fs::write(file, contents).unwrap();
+                }
+            }
+        }
+
+        let project = dir.join("project");
+        let conflicts =
+            apply_template_update(&dir.join("old"), &dir.join("new"), &project, dry_run).unwrap();
+        (project, conflicts)
+    }
+
+    fn read(project: &Path, path: &str) -> Option<String> {
+        fs::read_to_string(project.join(path)).ok()
+    }
+
+    #[test]
+    fn updates_files_the_project_did_not_change() {
+        let (project, conflicts) = update(
+            "update",
+            &[
+                ("README.md", Some(b"old"), Some(b"new"), Some(b"old")),
+                (
+                    "unchanged.md",
+                    Some(b"same"),
+                    Some(b"same"),
+                    Some(b"edited"),
+                ),
+                ("applied.md", Some(b"old"), Some(b"new"), Some(b"new")),
+            ],
+            false,
+        );
+
+        assert!(conflicts.is_empty());
+        assert_eq!(read(&project, "README.md").unwrap(), "new");
+        assert_eq!(read(&project, "unchanged.md").unwrap(), "edited");
+        assert_eq!(read(&project, "applied.md").unwrap(), "new");
+    }
+
+    #[test]
+    fn adds_files_new_in_the_template() {
+        let (project, conflicts) =
+            update("add", &[("src/lib.rs", None, Some(b"// new"), None)], false);
+
+        assert!(conflicts.is_empty());
+        assert_eq!(read(&project, "src/lib.rs").unwrap(), "// new");
+    }
+
+    #[test]
+    fn removes_files_removed_from_the_template_unless_changed() {
+        let (project, conflicts) = update(
+            "remove",
+            &[
+                ("removed.md", Some(b"old"), None, Some(b"old")),
+                ("kept.md", Some(b"old"), None, Some(b"edited")),
+            ],
+            false,
+        );
+
+        assert!(conflicts.is_empty());
+        assert_eq!(read(&project, "removed.md"), None);
+        assert_eq!(read(&project, "kept.md").unwrap(), "edited");
+    }
+
+    #[test]
+    fn skips_files_deleted_from_the_project() {
+        let (project, conflicts) = update(
+            "skip",
+            &[("deleted.md", Some(b"old"), Some(b"new"), None)],
+            false,
+        );
+
+        assert!(conflicts.is_empty());
+        assert_eq!(read(&project, "deleted.md"), None);
+    }
+
+    #[test]
+    fn merges_changes_of_the_project_and_the_template() {
+        let (project, conflicts) = update(
+            "merge",
+            &[(
+                "config.txt",
+                Some(b"one\ntwo\nthree\n"),
+                Some(b"one\ntwo\nthree (template)\n"),
+                Some(b"one (project)\ntwo\nthree\n"),
+            )],
+            false,
+        );
+
+        assert!(conflicts.is_empty());
+        assert_eq!(
+            read(&project, "config.txt").unwrap(),
+            "one (project)\ntwo\nthree (template)\n"
+        );
+    }
+
+    #[test]
+    fn marks_conflicting_changes() {
+        let (project, conflicts) = update(
+            "conflict",
+            &[
+                (
+                    "config.txt",
+                    Some(b"one\n"),
+                    Some(b"template\n"),
+                    Some(b"project\n"),
+                ),
+                // both added the file, so there is no common base
+                ("added.txt", None, Some(b"template\n"), Some(b"project\n")),
+            ],
+            false,
+        );
+
+        assert_eq!(
+            conflicts,
+            [project.join("added.txt"), project.join("config.txt")]
+        );
+        for path in ["config.txt", "added.txt"] {
+            let merged = read(&project, path).unwrap();
+            assert!(merged.contains("<<<<<<<"), "{}", merged);
+            assert!(
+                merged.contains("project\n=======\ntemplate\n"),
+                "{}",
+                merged
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_the_project_version_of_conflicting_binary_files() {
+        let (project, conflicts) = update(
+            "binary",
+            &[(
+                "logo.png",
+                Some(b"\0old"),
+                Some(b"\0template"),
+                Some(b"\0project"),
+            )],
+            false,
+        );
+
+        assert_eq!(conflicts, [project.join("logo.png")]);
+        assert_eq!(fs::read(project.join("logo.png")).unwrap(), b"\0project");
+    }
+
+    #[test]
+    fn changes_nothing_in_a_dry_run() {
+        let (project, conflicts) = update(
+            "dry-run",
+            &[
+                ("README.md", Some(b"old"), Some(b"new"), Some(b"old")),
+                ("added.md", None, Some(b"new"), None),
+                ("removed.md", Some(b"old"), None, Some(b"old")),
+                (
+                    "config.txt",
+                    Some(b"one\n"),
+                    Some(b"template\n"),
+                    Some(b"project\n"),
+                ),
+            ],
+            true,
+        );
+
+        assert_eq!(conflicts, [project.join("config.txt")]);
+        assert_eq!(read(&project, "README.md").unwrap(), "old");
+        assert_eq!(read(&project, "added.md"), None);
+        assert_eq!(read(&project, "removed.md").unwrap(), "old");
+        assert_eq!(read(&project, "config.txt").unwrap(), "project\n");
+    }
+}
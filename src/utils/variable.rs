@@ -1,3 +1,4 @@
+use crate::parser::placeholder::find_placeholders;
 use crate::parser::{Variable, VariableValue};
 use crate::utils::error::UtilsResult;
 use crate::utils::UtilsError;
@@ -11,56 +12,106 @@ fn prepare_command_string(value: &str, clean: &bool) -> String {
     }
 }
 
+const DEFAULT_LIST_SEPARATOR: &str = " ";
+
 pub fn replace_variables(input: &str, variables: &[Variable], clean: &bool) -> UtilsResult<String> {
-    let variables_map: HashMap<_, _> = variables
-        .iter()
-        .filter_map(|variable| variable.value.as_ref().map(|v| (variable.name.as_str(), v)))
-        .collect();
+    let variables_map = value_map(variables);
 
     let mut result = String::with_capacity(input.len() + 50);
     let mut last_idx = 0;
-    let bytes = input.as_bytes();
-
-    let mut idx = 0;
-    while idx < bytes.len() - 1 {
-        if bytes[idx..].starts_with(b"{{") {
-            if let Some(end) = input[idx + 2..].find("}}") {
-                let var_name = &input[idx + 2..idx + 2 + end];
-                result.push_str(&input[last_idx..idx]);
 
-                match variables_map.get(var_name) {
-                    Some(VariableValue::String(s)) => {
-                        result.push_str(&prepare_command_string(s, clean))
-                    }
-                    Some(VariableValue::Boolean(b)) => {
-                        result.push_str(&prepare_command_string(&b.to_string(), clean))
-                    }
-                    Some(VariableValue::Select(option)) => {
-                        if !option.is_empty() {
-                            result.push_str(&prepare_command_string(option, clean));
-                        } else {
-                            return Err(UtilsError::VariableNotSet(format!(
-                                "No value for {{{}}}",
-                                var_name
-                            )));
-                        }
-                    }
-                    None => {
-                        return Err(UtilsError::VariableNotSet(format!(
-                            "Variable {} not found",
-                            var_name
-                        )))
-                    }
-                }
+    for placeholder in find_placeholders(input) {
+        let (var_name, separator) = placeholder
+            .split()
+            .ok_or_else(|| UtilsError::InvalidPlaceholder(placeholder.expression.to_string()))?;
+        result.push_str(&input[last_idx..placeholder.range.start]);
 
-                last_idx = idx + 2 + end + 2;
-                idx = last_idx;
-                continue;
+        match variables_map.get(var_name) {
+            Some(value) => result.push_str(&render_value(var_name, value, separator, clean)?),
+            None => {
+                return Err(UtilsError::VariableNotSet(format!(
+                    "Variable {} not found",
+                    var_name
+                )))
             }
         }
-        idx += 1;
+
+        last_idx = placeholder.range.end;
     }
 
     result.push_str(&input[last_idx..]);
     Ok(result)
 }
+
+/// Replaces placeholders of variables in the contents of a template file. Unlike commands, files
+/// often contain `{{...}}` of other tools (GitHub Actions, Vue, Handlebars), so placeholders that
+/// are not a variable with a value, or use an unsupported filter, are left unchanged.
+pub fn render_contents(input: &str, variables: &[Variable]) -> UtilsResult<String> {
+    let variables_map = value_map(variables);
+
+    let mut result = String::with_capacity(input.len() + 50);
+    let mut last_idx = 0;
+
+    for placeholder in find_placeholders(input) {
+        let Some((var_name, separator)) = placeholder.split() else {
+            continue;
+        };
+        let Some(value) = variables_map.get(var_name) else {
+            continue;
+        };
+        result.push_str(&input[last_idx..placeholder.range.start]);
+        result.push_str(&render_value(var_name, value, separator, &false)?);
+        last_idx = placeholder.range.end;
+    }
+
+    result.push_str(&input[last_idx..]);
+    Ok(result)
+}
+
+fn value_map(variables: &[Variable]) -> HashMap<&str, &VariableValue> {
+    variables
+        .iter()
+        .filter_map(|variable| variable.value.as_ref().map(|v| (variable.name.as_str(), v)))
+        .collect()
+}
+
+fn render_value(
+    var_name: &str,
+    value: &VariableValue,
+    separator: Option<&str>,
+    clean: &bool,
+) -> UtilsResult<String> {
+    match value {
+        VariableValue::String(s) => Ok(prepare_command_string(s, clean)),
+        VariableValue::Boolean(b) => Ok(prepare_command_string(&b.to_string(), clean)),
+        VariableValue::Select(option) => {
+            if !option.is_empty() {
+                Ok(prepare_command_string(option, clean))
+            } else {
+                Err(UtilsError::VariableNotSet(format!(
+                    "No value for {{{}}}",
+                    var_name
+                )))
+            }
+        }
+        VariableValue::Integer(i) => Ok(i.to_string()),
+        VariableValue::Number(n) => Ok(n.to_string()),
+        VariableValue::MultiSelect(options) => {
+            let rendered: Vec<String> = options
+                .iter()
+                .map(|option| prepare_command_string(option, clean))
+                .collect();
+            Ok(rendered.join(separator.unwrap_or(DEFAULT_LIST_SEPARATOR)))
+        }
+    }
+}
+
+/// Replaces variables in the values of the configured environment variables.
+pub fn replace_environment_variables(
+    envs: &HashMap<String, String>,
+    variables: &[Variable],
+) -> UtilsResult<HashMap<String, String>> {
+    envs.iter()
+        .map(|(name, value)| Ok((name.clone(), replace_variables(value, variables, &false)?)))
+        .collect()
+}